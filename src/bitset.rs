@@ -0,0 +1,153 @@
+use std::fmt::Debug;
+
+/// Set of integers in `0..Self::CAPACITY`, stored as `W` 64-bit words.
+#[derive(Clone, Copy, PartialEq, Eq, Hash)]
+pub struct BitSet<const W: usize>([u64; W]);
+
+impl<const W: usize> Default for BitSet<W> {
+    fn default() -> Self {
+        Self([0; W])
+    }
+}
+
+impl<const W: usize> BitSet<W> {
+    /// Exclusive upper bound of the values the set can hold.
+    pub const CAPACITY: usize = 64 * W;
+
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add `v` to the set, returning whether it was not there yet.
+    ///
+    /// Panics if `v` is not below `Self::CAPACITY`.
+    pub fn insert(&mut self, v: usize) -> bool {
+        assert!(v < Self::CAPACITY, "{v} is out of the set's range");
+        let (word, bit) = (v / 64, 1 << (v % 64));
+        let inserted = self.0[word] & bit == 0;
+        self.0[word] |= bit;
+        inserted
+    }
+
+    /// Remove `v` from the set, returning whether it was there.
+    pub fn remove(&mut self, v: usize) -> bool {
+        let present = self.contains(v);
+        if present {
+            self.0[v / 64] &= !(1 << (v % 64));
+        }
+        present
+    }
+
+    pub fn contains(&self, v: usize) -> bool {
+        v < Self::CAPACITY && self.0[v / 64] & (1 << (v % 64)) != 0
+    }
+
+    pub fn len(&self) -> usize {
+        self.0.iter().map(|w| w.count_ones() as usize).sum()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.0.iter().all(|w| *w == 0)
+    }
+
+    pub fn union(&self, other: &Self) -> Self {
+        self.zip_with(other, |a, b| a | b)
+    }
+
+    pub fn intersection(&self, other: &Self) -> Self {
+        self.zip_with(other, |a, b| a & b)
+    }
+
+    pub fn difference(&self, other: &Self) -> Self {
+        self.zip_with(other, |a, b| a & !b)
+    }
+
+    /// Number of values in both sets, without building the intersection.
+    pub fn intersection_count(&self, other: &Self) -> usize {
+        self.0
+            .iter()
+            .zip(&other.0)
+            .map(|(a, b)| (a & b).count_ones() as usize)
+            .sum()
+    }
+
+    /// Values of the set, in increasing order.
+    pub fn iter(&self) -> impl Iterator<Item = usize> + '_ {
+        self.0.iter().enumerate().flat_map(|(idx, word)| {
+            let mut word = *word;
+            std::iter::from_fn(move || {
+                (word != 0).then(|| {
+                    let bit = word.trailing_zeros() as usize;
+                    word &= word - 1;
+                    idx * 64 + bit
+                })
+            })
+        })
+    }
+
+    fn zip_with(&self, other: &Self, f: impl Fn(u64, u64) -> u64) -> Self {
+        Self(std::array::from_fn(|i| f(self.0[i], other.0[i])))
+    }
+}
+
+impl<const W: usize> FromIterator<usize> for BitSet<W> {
+    fn from_iter<I: IntoIterator<Item = usize>>(iter: I) -> Self {
+        let mut set = Self::new();
+        set.extend(iter);
+        set
+    }
+}
+
+impl<const W: usize> Extend<usize> for BitSet<W> {
+    fn extend<I: IntoIterator<Item = usize>>(&mut self, iter: I) {
+        for v in iter {
+            self.insert(v);
+        }
+    }
+}
+
+impl<const W: usize> Debug for BitSet<W> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_set().entries(self.iter()).finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_set() {
+        let mut set = BitSet::<2>::new();
+        assert!(set.is_empty());
+        assert!(set.insert(3));
+        assert!(set.insert(127));
+        assert!(!set.insert(3));
+        assert!(set.contains(127));
+        assert!(!set.contains(64));
+        assert!(!set.contains(1000));
+        assert_eq!(set.len(), 2);
+        assert!(set.remove(3));
+        assert!(!set.remove(3));
+        assert_eq!(format!("{set:?}"), "{127}");
+    }
+
+    #[test]
+    fn test_operations() {
+        let a = [1, 5, 64, 100].into_iter().collect::<BitSet<2>>();
+        let b = [5, 6, 100].into_iter().collect::<BitSet<2>>();
+        assert_eq!(a.intersection_count(&b), 2);
+        assert_eq!(a.intersection(&b).iter().collect::<Vec<_>>(), vec![5, 100]);
+        assert_eq!(
+            a.union(&b).iter().collect::<Vec<_>>(),
+            vec![1, 5, 6, 64, 100]
+        );
+        assert_eq!(a.difference(&b).iter().collect::<Vec<_>>(), vec![1, 64]);
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_out_of_range() {
+        BitSet::<1>::new().insert(64);
+    }
+}
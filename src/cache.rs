@@ -0,0 +1,227 @@
+use std::{
+    collections::HashMap,
+    path::{Path, PathBuf},
+};
+
+use color_eyre::{
+    eyre::{Context, ContextCompat},
+    Result,
+};
+
+use nu_ansi_term::Color;
+
+use crate::Part;
+
+/// Default location of the answer cache, relative to the repository root.
+pub const DEFAULT_PATH: &str = ".cache/answers.tsv";
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Key {
+    day: usize,
+    part: u8,
+    version: String,
+    input_hash: u64,
+}
+
+impl Key {
+    pub fn new(day: usize, part: Part, version: &str, input: &str) -> Self {
+        Self {
+            day,
+            part: part.into(),
+            version: version.to_owned(),
+            input_hash: hash_input(input),
+        }
+    }
+}
+
+/// On-disk cache of answers, keyed by day, part, solution version and a hash of the input.
+///
+/// The cache is stored as a tab-separated file with one answer per line.
+pub struct Cache {
+    path: PathBuf,
+    entries: HashMap<Key, String>,
+    lookup: bool,
+    dirty: bool,
+}
+
+impl Cache {
+    /// Load the cache from `path`. A missing file is treated as an empty cache.
+    pub fn load(path: impl AsRef<Path>) -> Result<Self> {
+        let path = path.as_ref().to_path_buf();
+        let entries = match std::fs::read_to_string(&path) {
+            Ok(s) => s
+                .lines()
+                .enumerate()
+                .map(|(idx, line)| {
+                    parse_entry(line)
+                        .wrap_err_with(|| format!("Invalid cache entry on line {}", idx + 1))
+                })
+                .collect::<Result<_>>()?,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => HashMap::new(),
+            Err(e) => return Err(e).wrap_err("Failed to load cache file"),
+        };
+
+        Ok(Self {
+            path,
+            entries,
+            lookup: true,
+            dirty: false,
+        })
+    }
+
+    /// Load the cache from `path`, starting from an empty cache (with a warning) if the file
+    /// can't be read or is corrupt, e.g. because it was written by an older version. The file is
+    /// then overwritten on the next save.
+    pub fn load_or_empty(path: impl AsRef<Path>) -> Self {
+        let path = path.as_ref();
+        Self::load(path).unwrap_or_else(|e| {
+            eprintln!(
+                "{}",
+                Color::Yellow.paint(format!("Ignoring cache file {}: {e:#}", path.display()))
+            );
+            Self {
+                path: path.to_path_buf(),
+                entries: HashMap::new(),
+                lookup: true,
+                dirty: false,
+            }
+        })
+    }
+
+    /// Never return cached answers, but still record freshly computed ones.
+    pub fn force_recompute(mut self) -> Self {
+        self.lookup = false;
+        self
+    }
+
+    pub fn get(&self, key: &Key) -> Option<&str> {
+        if self.lookup {
+            self.entries.get(key).map(|s| s.as_str())
+        } else {
+            None
+        }
+    }
+
+    pub fn insert(&mut self, key: Key, answer: &str) {
+        // Keep the file format trivial: answers spanning several lines or containing tabs are
+        // simply not cached.
+        if answer.contains(['\t', '\n']) {
+            return;
+        }
+        if self.entries.get(&key).map(|s| s.as_str()) != Some(answer) {
+            self.entries.insert(key, answer.to_owned());
+            self.dirty = true;
+        }
+    }
+
+    /// Write the cache back to disk if anything changed.
+    pub fn save(&mut self) -> Result<()> {
+        if !self.dirty {
+            return Ok(());
+        }
+        if let Some(dir) = self.path.parent() {
+            std::fs::create_dir_all(dir).wrap_err("Failed to create cache directory")?;
+        }
+
+        let mut entries = self.entries.iter().collect::<Vec<_>>();
+        entries.sort_by(|(a, _), (b, _)| {
+            (a.day, a.part, &a.version, a.input_hash).cmp(&(
+                b.day,
+                b.part,
+                &b.version,
+                b.input_hash,
+            ))
+        });
+        let contents = entries
+            .into_iter()
+            .map(|(key, answer)| {
+                format!(
+                    "{}\t{}\t{}\t{:016x}\t{answer}\n",
+                    key.day, key.part, key.version, key.input_hash
+                )
+            })
+            .collect::<String>();
+        std::fs::write(&self.path, contents).wrap_err("Failed to write cache file")?;
+        self.dirty = false;
+
+        Ok(())
+    }
+}
+
+fn parse_entry(line: &str) -> Result<(Key, String)> {
+    let mut fields = line.splitn(5, '\t');
+    let mut next = || fields.next().wrap_err("Missing field");
+    let day = next()?.parse()?;
+    let part = next()?.parse()?;
+    let version = next()?.to_owned();
+    let input_hash = u64::from_str_radix(next()?, 16)?;
+    let answer = next()?.to_owned();
+
+    Ok((
+        Key {
+            day,
+            part,
+            version,
+            input_hash,
+        },
+        answer,
+    ))
+}
+
+/// 64-bit FNV-1a hash of the input. Unlike `DefaultHasher`, this is guaranteed to be stable
+/// across Rust releases, which matters since the hashes are persisted.
+pub fn hash_input(input: &str) -> u64 {
+    input.bytes().fold(0xcbf29ce484222325, |hash, b| {
+        (hash ^ b as u64).wrapping_mul(0x100000001b3)
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_roundtrip() {
+        let path = std::env::temp_dir().join(format!("aoc2023-cache-{}.tsv", std::process::id()));
+        let _ = std::fs::remove_file(&path);
+
+        let key = Key::new(5, Part::P2, "1", "seeds: 79 14 55 13");
+        let mut cache = Cache::load(&path).unwrap();
+        assert_eq!(cache.get(&key), None);
+        cache.insert(key.clone(), "46");
+        cache.save().unwrap();
+
+        let cache = Cache::load(&path).unwrap();
+        assert_eq!(cache.get(&key), Some("46"));
+        assert_eq!(
+            cache.get(&Key::new(5, Part::P2, "2", "seeds: 79 14 55 13")),
+            None
+        );
+        assert_eq!(
+            cache.get(&Key::new(5, Part::P2, "1", "seeds: 79 14 55 14")),
+            None
+        );
+
+        let cache = cache.force_recompute();
+        assert_eq!(cache.get(&key), None);
+
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn test_corrupt() {
+        let path =
+            std::env::temp_dir().join(format!("aoc2023-cache-corrupt-{}.tsv", std::process::id()));
+        std::fs::write(&path, "5\t2\t1\tnot a hash\t46\n").unwrap();
+        assert!(Cache::load(&path).is_err());
+
+        let key = Key::new(5, Part::P2, "1", "seeds: 79 14 55 13");
+        let mut cache = Cache::load_or_empty(&path);
+        assert_eq!(cache.get(&key), None);
+        cache.insert(key.clone(), "46");
+        cache.save().unwrap();
+        assert_eq!(Cache::load(&path).unwrap().get(&key), Some("46"));
+
+        std::fs::remove_file(&path).unwrap();
+    }
+}
@@ -0,0 +1,216 @@
+//! Cycle detection in eventually periodic sequences.
+//!
+//! A sequence `x0, x1, x2, ...` where each element only depends on the previous one eventually
+//! loops: after a `tail` of non-repeating elements, it repeats with some `period`. The sequence
+//! can be given either as an iterator (which must be `Clone`, so that it can be walked several
+//! times), or as an initial state and a transition function.
+
+use std::iter::successors;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Cycle {
+    /// Index of the first element that is part of the cycle
+    pub tail: usize,
+    /// Length of the cycle
+    pub period: usize,
+}
+
+impl Cycle {
+    /// Index of the earliest element equal to the `n`th one.
+    pub fn reduce(&self, n: u64) -> usize {
+        let tail = self.tail as u64;
+        if n < tail {
+            n as usize
+        } else {
+            self.tail + ((n - tail) % self.period as u64) as usize
+        }
+    }
+
+    /// Indices of the elements of the cycle, i.e. `tail..tail + period`.
+    pub fn indices(&self) -> std::ops::Range<usize> {
+        self.tail..self.tail + self.period
+    }
+}
+
+/// Sequence of states `x0, f(x0), f(f(x0)), ...`.
+pub fn states<S>(x0: S, f: impl Fn(&S) -> S + Clone) -> impl Iterator<Item = S> + Clone
+where
+    S: Clone,
+{
+    successors(Some(x0), move |x| Some(f(x)))
+}
+
+/// Floyd's "tortoise and hare" algorithm. Returns `None` if the iterator ends.
+pub fn floyd_iter<I>(iter: I) -> Option<Cycle>
+where
+    I: Iterator + Clone,
+    I::Item: PartialEq,
+{
+    // Find i > 0 such that x_i == x_2i
+    let mut tortoise = iter.clone();
+    let mut hare = iter.clone();
+    tortoise.next()?;
+    hare.next()?;
+    let mut h = loop {
+        let t = tortoise.next()?;
+        hare.next()?;
+        let h = hare.next()?;
+        if t == h {
+            break h;
+        }
+    };
+
+    // i is a multiple of the period, so walking from x0 and x_i in lockstep, the first match
+    // is at the start of the cycle
+    let mut tortoise = iter;
+    let mut t = tortoise.next()?;
+    let mut tail = 0;
+    while t != h {
+        t = tortoise.next()?;
+        h = hare.next()?;
+        tail += 1;
+    }
+
+    let mut period = 1;
+    let mut hare = tortoise;
+    while hare.next()? != t {
+        period += 1;
+    }
+
+    Some(Cycle { tail, period })
+}
+
+/// Brent's algorithm, which usually needs fewer steps than Floyd's. Returns `None` if the
+/// iterator ends.
+pub fn brent_iter<I>(iter: I) -> Option<Cycle>
+where
+    I: Iterator + Clone,
+    I::Item: PartialEq + Clone,
+{
+    // Look for the period by comparing against elements at increasing powers of two
+    let mut hare = iter.clone();
+    let mut t = hare.next()?;
+    let mut h = hare.next()?;
+    let (mut power, mut period) = (1, 1);
+    while t != h {
+        if power == period {
+            t = h.clone();
+            power *= 2;
+            period = 0;
+        }
+        h = hare.next()?;
+        period += 1;
+    }
+
+    // Walk from x0 and x_period in lockstep: they first match at the start of the cycle
+    let mut tortoise = iter.clone();
+    let mut hare = iter.skip(period);
+    let (mut t, mut h) = (tortoise.next()?, hare.next()?);
+    let mut tail = 0;
+    while t != h {
+        t = tortoise.next()?;
+        h = hare.next()?;
+        tail += 1;
+    }
+
+    Some(Cycle { tail, period })
+}
+
+/// Floyd's algorithm on the states `x0, f(x0), f(f(x0)), ...`.
+pub fn floyd<S>(x0: S, f: impl Fn(&S) -> S + Clone) -> Cycle
+where
+    S: PartialEq + Clone,
+{
+    floyd_iter(states(x0, f)).expect("sequence is infinite")
+}
+
+/// Brent's algorithm on the states `x0, f(x0), f(f(x0)), ...`.
+pub fn brent<S>(x0: S, f: impl Fn(&S) -> S + Clone) -> Cycle
+where
+    S: PartialEq + Clone,
+{
+    brent_iter(states(x0, f)).expect("sequence is infinite")
+}
+
+/// Indices of the elements inside the cycle that match `pred`.
+pub fn matches_in_cycle<I>(
+    iter: I,
+    cycle: &Cycle,
+    mut pred: impl FnMut(&I::Item) -> bool,
+) -> Vec<usize>
+where
+    I: Iterator,
+{
+    iter.enumerate()
+        .skip(cycle.tail)
+        .take(cycle.period)
+        .filter_map(|(idx, x)| pred(&x).then_some(idx))
+        .collect()
+}
+
+/// The `n`th element of an eventually periodic iterator, without walking all the way to it.
+pub fn nth<I>(mut iter: I, n: u64) -> Option<I::Item>
+where
+    I: Iterator + Clone,
+    I::Item: PartialEq + Clone,
+{
+    let cycle = brent_iter(iter.clone())?;
+    iter.nth(cycle.reduce(n))
+}
+
+/// The state after `n` applications of `f` to `x0`, without computing all the steps.
+pub fn nth_state<S>(x0: S, f: impl Fn(&S) -> S + Clone, n: u64) -> S
+where
+    S: PartialEq + Clone,
+{
+    nth(states(x0, f), n).expect("sequence is infinite")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // 0, 1, 2, then 3, 4, 5, 6, 7 forever
+    fn f(x: &u64) -> u64 {
+        if *x < 7 {
+            x + 1
+        } else {
+            3
+        }
+    }
+
+    #[test]
+    fn test_detect() {
+        let expected = Cycle { tail: 3, period: 5 };
+        assert_eq!(floyd(0, f), expected);
+        assert_eq!(brent(0, f), expected);
+        assert_eq!(floyd(5, f), Cycle { tail: 0, period: 5 });
+        assert_eq!(brent(5, f), Cycle { tail: 0, period: 5 });
+        assert_eq!(brent(1, |_| 1), Cycle { tail: 0, period: 1 });
+
+        let seq = [1, 2, 3, 1, 2, 3];
+        assert_eq!(
+            floyd_iter(seq.iter().cycle()),
+            Some(Cycle { tail: 0, period: 3 })
+        );
+        assert_eq!(brent_iter(seq.iter()), None);
+    }
+
+    #[test]
+    fn test_matches() {
+        let cycle = brent(0, f);
+        assert_eq!(
+            matches_in_cycle(states(0, f), &cycle, |x| x % 2 == 0),
+            vec![4, 6]
+        );
+    }
+
+    #[test]
+    fn test_nth() {
+        let cycle = brent(0, f);
+        assert_eq!(cycle.reduce(2), 2);
+        assert_eq!(cycle.reduce(8), 3);
+        assert_eq!(nth_state(0, f, 1_000_000_000_000), 5);
+        assert_eq!(nth_state(0, f, 12), states(0, f).nth(12).unwrap());
+    }
+}
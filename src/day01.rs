@@ -1,4 +1,6 @@
-use aoc2023::Day;
+use std::io::BufRead;
+
+use aoc2023::{rng::Rng, Day};
 use color_eyre::Result;
 use nom::{
     branch::alt,
@@ -9,39 +11,48 @@ use nom::{
 };
 
 inventory::submit! {
-    Day::new(1, part1, part2)
+    Day::new(1, part1, part2).with_streaming(part1_stream, part2_stream).with_generator(generate)
 }
 
 fn part1(input: &str) -> Result<String> {
-    let total: u32 = input
+    part1_stream(&mut input.as_bytes())
+}
+
+fn part2(input: &str) -> Result<String> {
+    part2_stream(&mut input.as_bytes())
+}
+
+fn part1_stream(input: &mut dyn BufRead) -> Result<String> {
+    let total = input
         .lines()
         .map(|line| {
-            let digits = line
+            let digits = line?
                 .chars()
                 .filter_map(|c| c.to_digit(10))
                 .collect::<Vec<_>>();
             let d1 = digits.first().copied().expect("Line has no digits!");
             let d2 = digits.last().copied().expect("Line has no digits!");
-            d1 * 10 + d2
+            Ok(d1 * 10 + d2)
         })
-        .sum();
+        .sum::<Result<u32>>()?;
 
     Ok(format!("{total}"))
 }
 
-fn part2(input: &str) -> Result<String> {
-    let total: u32 = input
+fn part2_stream(input: &mut dyn BufRead) -> Result<String> {
+    let total = input
         .lines()
         .map(|line| {
+            let line = line?;
             let bytes = line.as_bytes();
             let digits = (0..bytes.len())
                 .filter_map(|i| parse_digit(&bytes[i..]).ok().map(|(_rest, digit)| digit))
                 .collect::<Vec<_>>();
             let d1 = digits.first().copied().expect("Line has no digits!");
             let d2 = digits.last().copied().expect("Line has no digits!");
-            d1 * 10 + d2
+            Ok(d1 * 10 + d2)
         })
-        .sum();
+        .sum::<Result<u32>>()?;
 
     Ok(format!("{total}"))
 }
@@ -62,3 +73,62 @@ fn parse_digit(input: &[u8]) -> IResult<&[u8], u32> {
         }),
     ))(input)
 }
+
+fn generate(size: usize, rng: &mut Rng) -> String {
+    const WORDS: [&str; 9] = [
+        "one", "two", "three", "four", "five", "six", "seven", "eight", "nine",
+    ];
+
+    let mut input = String::new();
+    for _ in 0..size {
+        let mut line = String::new();
+        for _ in 0..rng.between(1, 8) {
+            match rng.below(3) {
+                0 => line.push(char::from(b'1' + rng.below(9) as u8)),
+                1 => line.push_str(WORDS[rng.below(9) as usize]),
+                _ => line
+                    .extend((0..rng.between(1, 5)).map(|_| char::from(b'a' + rng.below(26) as u8))),
+            }
+        }
+        // Part 1 needs at least one actual digit on every line
+        if !line.bytes().any(|b| b.is_ascii_digit()) {
+            line.push(char::from(b'1' + rng.below(9) as u8));
+        }
+        input.push_str(&line);
+        input.push('\n');
+    }
+    input
+}
+
+#[cfg(test)]
+mod tests {
+    use aoc2023::rng::check_generator;
+    use color_eyre::eyre::ensure;
+
+    use super::*;
+
+    #[test]
+    fn test_generate() {
+        fn lines(input: &str) -> Result<usize> {
+            for line in input.lines() {
+                ensure!(
+                    line.bytes().any(|b| b.is_ascii_digit()),
+                    "Line {line:?} has no digits"
+                );
+                ensure!(
+                    line.bytes().all(|b| b.is_ascii_alphanumeric()),
+                    "Line {line:?} has unexpected characters"
+                );
+            }
+            Ok(input.lines().count())
+        }
+
+        check_generator(generate, &[10, 100, 1000], lines);
+        assert_eq!(lines(&generate(20, &mut Rng::new(0))).unwrap(), 20);
+        for seed in 0..10 {
+            let input = generate(20, &mut Rng::new(seed));
+            part1(&input).unwrap();
+            part2(&input).unwrap();
+        }
+    }
+}
@@ -1,7 +1,8 @@
-use std::ops::Add;
+use std::{io::BufRead, ops::Add};
 
-use aoc2023::Day;
+use aoc2023::{parse, rng::Rng, Day};
 use color_eyre::Result;
+use itertools::Itertools;
 use nom::{
     branch::alt,
     bytes::complete::tag,
@@ -13,34 +14,39 @@ use nom::{
 };
 
 inventory::submit! {
-    Day::new(2, part1, part2)
+    Day::new(2, part1, part2).with_streaming(part1_stream, part2_stream).with_generator(generate)
 }
 
 fn part1(input: &str) -> Result<String> {
-    let total: u32 = input
-        .lines()
-        .map(|line| {
-            let (_, game) = parse_game(line).expect("Invalid line");
-            game
+    part1_stream(&mut input.as_bytes())
+}
+
+fn part2(input: &str) -> Result<String> {
+    part2_stream(&mut input.as_bytes())
+}
+
+fn part1_stream(input: &mut dyn BufRead) -> Result<String> {
+    let total = parse::read_lines(input, parse_game)
+        .map(|game| {
+            let game = game?;
+            if game.matches(12, 13, 14) {
+                Ok(game.0)
+            } else {
+                Ok(0)
+            }
         })
-        .filter_map(|game| game.matches(12, 13, 14).then_some(game.0))
-        .sum();
+        .sum::<Result<u32>>()?;
 
     Ok(format!("{total}"))
 }
 
-fn part2(input: &str) -> Result<String> {
-    let total: u32 = input
-        .lines()
-        .map(|line| {
-            let (_, game) = parse_game(line).expect("Invalid line");
-            game
-        })
+fn part2_stream(input: &mut dyn BufRead) -> Result<String> {
+    let total = parse::read_lines(input, parse_game)
         .map(|game| {
-            let set = game.min_color_set();
-            set.power()
+            let set = game?.min_color_set();
+            Ok(set.power())
         })
-        .sum();
+        .sum::<Result<u32>>()?;
 
     Ok(format!("{total}"))
 }
@@ -143,17 +149,36 @@ fn parse_round(input: &str) -> IResult<&str, Round> {
 fn parse_game(input: &str) -> IResult<&str, Game> {
     map(
         tuple((
-            tag("Game "),
-            u32,
-            tag(": "),
+            parse::header("Game"),
             separated_list1(tag("; "), parse_round),
         )),
-        |(_, n, _, rounds)| Game(n, rounds),
+        |(n, rounds)| Game(n, rounds),
     )(input)
 }
 
+fn generate(size: usize, rng: &mut Rng) -> String {
+    let mut input = String::new();
+    for n in 1..=size {
+        let rounds = (0..rng.between(1, 6))
+            .map(|_| {
+                let mut colors = ["red", "green", "blue"];
+                rng.shuffle(&mut colors);
+                colors[..rng.between(1, 3) as usize]
+                    .iter()
+                    .map(|color| format!("{} {color}", rng.between(1, 20)))
+                    .join(", ")
+            })
+            .join("; ");
+        input.push_str(&format!("Game {n}: {rounds}\n"));
+    }
+    input
+}
+
 #[cfg(test)]
 mod tests {
+    use aoc2023::rng::check_generator;
+    use color_eyre::eyre::ensure;
+
     use super::*;
 
     #[test]
@@ -168,4 +193,24 @@ Game 5: 6 red, 1 blue, 3 green; 2 blue, 1 red, 2 green",
         .unwrap();
         assert_eq!(res, "2286")
     }
+
+    #[test]
+    fn test_generate() {
+        fn games(input: &str) -> Result<usize> {
+            let games =
+                parse::read_lines(&mut input.as_bytes(), parse_game).collect::<Result<Vec<_>>>()?;
+            for (idx, game) in games.iter().enumerate() {
+                ensure!(game.0 as usize == idx + 1, "Game {} out of order", game.0);
+            }
+            Ok(games.len())
+        }
+
+        check_generator(generate, &[10, 100, 1000], games);
+        assert_eq!(games(&generate(20, &mut Rng::new(0))).unwrap(), 20);
+        for seed in 0..10 {
+            let input = generate(20, &mut Rng::new(seed));
+            part1(&input).unwrap();
+            part2(&input).unwrap();
+        }
+    }
 }
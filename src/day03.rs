@@ -1,14 +1,19 @@
-use aoc2023::Day;
+use aoc2023::{
+    grid::Grid,
+    point::{UPoint, Vec2},
+    rng::Rng,
+    Day,
+};
 use color_eyre::Result;
 use itertools::Itertools;
-use regex::{self, Regex};
+use regex::bytes::Regex;
 
 inventory::submit! {
-    Day::new(3, part1, part2)
+    Day::new(3, part1, part2).with_generator(generate)
 }
 
 fn part1(input: &str) -> Result<String> {
-    let schematics = Schematics::new(input);
+    let schematics = Schematics::new(input)?;
 
     let total: u32 = schematics.part_numbers_sum();
 
@@ -16,9 +21,9 @@ fn part1(input: &str) -> Result<String> {
 }
 
 fn part2(input: &str) -> Result<String> {
-    let schematics = Schematics::new(input);
+    let schematics = Schematics::new(input)?;
 
-    let total: u32 = schematics.gear_ratios().into_iter().sum();
+    let total: u64 = schematics.gear_ratios().into_iter().sum();
 
     Ok(format!("{total}"))
 }
@@ -31,28 +36,27 @@ pub struct NumSpan {
     y: usize,
 }
 
-pub struct Schematics<'s> {
-    width: usize,
-    height: usize,
-    lines: Vec<&'s str>,
+pub struct Schematics {
+    grid: Grid<u8>,
     num_spans: Vec<NumSpan>,
 }
 
-impl<'s> Schematics<'s> {
-    pub fn new(input: &'s str) -> Self {
-        let lines = input.lines().collect::<Vec<_>>();
-        let width = lines[0].len();
-        let height = lines.len();
+impl Schematics {
+    pub fn new(input: &str) -> Result<Self> {
+        let grid = Grid::<u8>::parse(input)?;
         let re = Regex::new(r"\d+").expect("Invalid regex");
 
-        let num_spans = lines
-            .iter()
+        let num_spans = grid
+            .rows()
             .enumerate()
-            .flat_map(|(y, line)| {
-                re.find_iter(line).map(move |m| {
+            .flat_map(|(y, row)| {
+                re.find_iter(row).map(move |m| {
                     let x_start = m.start();
                     let x_end = x_start + m.len() - 1;
-                    let num = m.as_str().parse().expect("should be a number");
+                    let num = std::str::from_utf8(m.as_bytes())
+                        .expect("digits are ASCII")
+                        .parse()
+                        .expect("should be a number");
                     NumSpan {
                         num,
                         x_start,
@@ -63,12 +67,7 @@ impl<'s> Schematics<'s> {
             })
             .collect::<Vec<_>>();
 
-        Self {
-            width,
-            height,
-            lines,
-            num_spans,
-        }
+        Ok(Self { grid, num_spans })
     }
 
     pub fn part_numbers(&self) -> Vec<u32> {
@@ -85,33 +84,33 @@ impl<'s> Schematics<'s> {
         self.part_numbers().into_iter().sum()
     }
 
-    pub fn gear_ratios(&self) -> Vec<u32> {
+    pub fn gear_ratios(&self) -> Vec<u64> {
         let mut potential_gears = self
             .num_spans
             .iter()
             .filter_map(|n| {
                 self.star_neighbour(n.x_start, n.x_end, n.y)
-                    .map(|(x, y)| (n.num, (x, y)))
+                    .map(|pos| (n.num, pos))
             })
             .collect::<Vec<_>>();
         potential_gears.sort_by_key(|v| v.1);
         let stars_grouped_by_pos = potential_gears.into_iter().group_by(|(_n, pos)| *pos);
         stars_grouped_by_pos
             .into_iter()
-            .map(|(_pos, group)| group.map(|g| g.0).collect::<Vec<_>>())
+            .map(|(_pos, group)| group.map(|g| g.0 as u64).collect::<Vec<_>>())
             .filter_map(|nums| (nums.len() == 2).then_some(nums.into_iter().product()))
             .collect::<Vec<_>>()
     }
 
     fn is_neighbour_symbol(&self, x_start: usize, x_end: usize, y: usize) -> bool {
         self.span_neighbours_pos(x_start, x_end, y)
-            .map(|(i, j)| self.at(i, j))
+            .map(|pos| self.grid[pos])
             .any(|c| !c.is_ascii_digit() && c != b'.')
     }
 
-    fn star_neighbour(&self, x_start: usize, x_end: usize, y: usize) -> Option<(usize, usize)> {
+    fn star_neighbour(&self, x_start: usize, x_end: usize, y: usize) -> Option<UPoint> {
         self.span_neighbours_pos(x_start, x_end, y)
-            .find(|(i, j)| self.at(*i, *j) == b'*')
+            .find(|pos| self.grid[*pos] == b'*')
     }
 
     fn span_neighbours_pos(
@@ -119,24 +118,53 @@ impl<'s> Schematics<'s> {
         x_start: usize,
         x_end: usize,
         y: usize,
-    ) -> impl Iterator<Item = (usize, usize)> {
-        let left_bound = if x_start > 0 { x_start - 1 } else { 0 };
-        let right_bound = (x_end + 1).min(self.width - 1);
-        let top_bound = if y > 0 { y - 1 } else { 0 };
-        let bottom_bound = (y + 1).min(self.height - 1);
-
-        (left_bound..=right_bound)
-            .cartesian_product(top_bound..=bottom_bound)
-            .filter(move |(i, j)| *i < x_start || *i > x_end || *j != y)
+    ) -> impl Iterator<Item = UPoint> + '_ {
+        let len = (x_end - x_start) as i64;
+
+        (-1..=len + 1)
+            .cartesian_product(-1..=1)
+            .filter(move |(dx, dy)| *dx < 0 || *dx > len || *dy != 0)
+            .filter_map(move |(dx, dy)| {
+                self.grid.offset(UPoint::new(x_start, y), Vec2::new(dx, dy))
+            })
     }
+}
 
-    fn at(&self, x: usize, y: usize) -> u8 {
-        self.lines[y].as_bytes()[x]
+fn generate(size: usize, rng: &mut Rng) -> String {
+    const SYMBOLS: &[u8] = b"*#+$/@%=&-";
+
+    let size = size.max(1);
+    let mut input = String::new();
+    for _ in 0..size {
+        let mut line = Vec::with_capacity(size);
+        while line.len() < size {
+            let remaining = size - line.len();
+            let roll = rng.below(10);
+            if roll < 2 {
+                // Numbers are always followed by a non-digit so they don't merge with the next one
+                let len = rng.between(1, 3).min(remaining as u64 - 1) as usize;
+                if len > 0 {
+                    line.push(b'1' + rng.below(9) as u8);
+                    line.extend((1..len).map(|_| b'0' + rng.below(10) as u8));
+                }
+                line.push(b'.');
+            } else if roll < 3 {
+                line.push(*rng.choose(SYMBOLS));
+            } else {
+                line.push(b'.');
+            }
+        }
+        input.push_str(std::str::from_utf8(&line).expect("ASCII"));
+        input.push('\n');
     }
+    input
 }
 
 #[cfg(test)]
 mod tests {
+    use aoc2023::rng::check_generator;
+    use color_eyre::eyre::ensure;
+
     use super::*;
 
     #[test]
@@ -152,8 +180,29 @@ mod tests {
 ......755.
 ...$.*....
 .664.598..",
-        );
+        )
+        .unwrap();
         let total = s.part_numbers_sum();
         assert_eq!(4361, total);
     }
+
+    #[test]
+    fn test_generate() {
+        fn rows(input: &str) -> Result<usize> {
+            let schematics = Schematics::new(input)?;
+            ensure!(
+                schematics.grid.width() == schematics.grid.height(),
+                "The schematics should be square"
+            );
+            Ok(schematics.grid.height())
+        }
+
+        check_generator(generate, &[10, 50, 200], rows);
+        assert_eq!(rows(&generate(20, &mut Rng::new(0))).unwrap(), 20);
+        for seed in 0..10 {
+            let input = generate(20, &mut Rng::new(seed));
+            part1(&input).unwrap();
+            part2(&input).unwrap();
+        }
+    }
 }
@@ -1,64 +1,79 @@
-use std::collections::{HashSet, VecDeque};
+use std::{collections::VecDeque, io::BufRead};
 
-use aoc2023::Day;
-use color_eyre::Result;
+use aoc2023::{bitset::BitSet, parse, rng::Rng, Day};
+use color_eyre::{eyre::ensure, Result};
+use itertools::Itertools;
 use nom::{
     bytes::complete::tag,
     character::complete::{space1, u32},
-    combinator::map,
-    multi::separated_list1,
-    sequence::{delimited, tuple},
+    combinator::{map, verify},
+    sequence::tuple,
     IResult,
 };
 
 inventory::submit! {
     Day::new(4, part1, part2)
+        .with_version("1")
+        .with_streaming(part1_stream, part2_stream)
+        .with_generator(generate)
 }
 
 fn part1(input: &str) -> Result<String> {
-    let cards = input
-        .lines()
-        .map(|line| parse_card(line).expect("invalid line!").1)
-        .collect::<Vec<_>>();
-
-    let total = cards.into_iter().map(|c| c.value()).sum::<u32>();
-    Ok(format!("{total}"))
+    part1_stream(&mut input.as_bytes())
 }
 
 fn part2(input: &str) -> Result<String> {
-    let cards = input
-        .lines()
-        .map(|line| parse_card(line).expect("invalid line!").1)
-        .collect::<Vec<_>>();
+    part2_stream(&mut input.as_bytes())
+}
 
-    let mut to_process = cards.iter().map(|c| c.card_num).collect::<VecDeque<_>>();
+fn part1_stream(input: &mut dyn BufRead) -> Result<String> {
+    let total = parse::read_lines(input, parse_card)
+        .map(|card| Ok(card?.value()))
+        .sum::<Result<u32>>()?;
+    Ok(format!("{total}"))
+}
+
+fn part2_stream(input: &mut dyn BufRead) -> Result<String> {
+    // Rather than queueing every copy, keep track of how many extra copies each of the upcoming
+    // cards has won so far. Since a card only ever wins copies of the next few cards, this only
+    // needs as much memory as the maximum number of matches on a card.
+    let mut extra_copies: VecDeque<u64> = VecDeque::new();
 
     let mut count = 0;
-    while let Some(n) = to_process.pop_front() {
-        count += 1;
-        let c = &cards[n as usize - 1];
-        let matches = c.matches() as u32;
-        if matches != 0 {
-            let copies_range = n + 1..=n + matches;
-            to_process.extend(copies_range);
+    for (idx, card) in parse::read_lines(input, parse_card).enumerate() {
+        let c = card?;
+        ensure!(
+            c.card_num as usize == idx + 1,
+            "Cards must be listed in order (found card {} on line {})",
+            c.card_num,
+            idx + 1
+        );
+        let copies = 1 + extra_copies.pop_front().unwrap_or(0);
+        count += copies;
+        let matches = c.matches();
+        if extra_copies.len() < matches {
+            extra_copies.resize(matches, 0);
+        }
+        for n in extra_copies.iter_mut().take(matches) {
+            *n += copies;
         }
     }
 
     Ok(format!("{count}"))
 }
 
+/// Card numbers are below 100, so they fit in a two-word bitset.
+type NumSet = BitSet<2>;
+
 struct Card {
     card_num: u32,
-    winning_nums: HashSet<u32>,
-    nums: Vec<u32>,
+    winning_nums: NumSet,
+    nums: NumSet,
 }
 
 impl Card {
     pub fn matches(&self) -> usize {
-        self.nums
-            .iter()
-            .filter(|n| self.winning_nums.contains(*n))
-            .count()
+        self.nums.intersection_count(&self.winning_nums)
     }
 
     pub fn value(&self) -> u32 {
@@ -74,21 +89,65 @@ impl Card {
 fn parse_card(input: &str) -> IResult<&str, Card> {
     map(
         tuple((
-            delimited(tuple((tag("Card"), space1)), u32, tuple((tag(":"), space1))),
-            separated_list1(space1, u32),
+            parse::header("Card"),
+            parse::numbers(card_number),
             tuple((space1, tag("|"), space1)),
-            separated_list1(space1, u32),
+            parse::numbers(card_number),
         )),
         |(n, winning, _, nums)| Card {
             card_num: n,
             winning_nums: winning.into_iter().collect(),
-            nums,
+            nums: nums.into_iter().collect(),
         },
     )(input)
 }
 
+fn card_number(input: &str) -> IResult<&str, usize> {
+    map(verify(u32, |n| (*n as usize) < NumSet::CAPACITY), |n| {
+        n as usize
+    })(input)
+}
+
+fn generate(size: usize, rng: &mut Rng) -> String {
+    const WINNING: usize = 10;
+    const NUMS: usize = 25;
+
+    let mut input = String::new();
+    for n in 1..=size {
+        let mut pool = (1..100).collect::<Vec<u32>>();
+        rng.shuffle(&mut pool);
+        let (winning, others) = pool.split_at(WINNING);
+
+        // Keep the average number of matches below 1, otherwise the number of copies grows
+        // exponentially with the number of cards. Cards can't win copies past the end of the table.
+        let matches = if rng.chance(0.75) {
+            0
+        } else {
+            rng.between(1, 3) as usize
+        };
+        let matches = matches.min(size - n);
+        let mut nums = winning[..matches]
+            .iter()
+            .chain(&others[..NUMS - matches])
+            .copied()
+            .collect::<Vec<_>>();
+        rng.shuffle(&mut nums);
+
+        let fmt_nums = |nums: &[u32]| nums.iter().map(|n| format!("{n:>2}")).join(" ");
+        input.push_str(&format!(
+            "Card {n:>3}: {} | {}\n",
+            fmt_nums(winning),
+            fmt_nums(&nums)
+        ));
+    }
+    input
+}
+
 #[cfg(test)]
 mod tests {
+    use aoc2023::rng::check_generator;
+    use color_eyre::eyre::ensure;
+
     use super::*;
 
     #[test]
@@ -120,4 +179,29 @@ Card 6: 31 18 13 56 72 | 74 77 10 23 35 67 36 11",
 
         assert_eq!(total, "30");
     }
+
+    #[test]
+    fn test_generate() {
+        fn cards(input: &str) -> Result<usize> {
+            let cards =
+                parse::read_lines(&mut input.as_bytes(), parse_card).collect::<Result<Vec<_>>>()?;
+            // Cards can't win copies of cards past the end of the table
+            for (idx, card) in cards.iter().enumerate() {
+                ensure!(
+                    idx + card.matches() < cards.len(),
+                    "Card {} wins cards past the end",
+                    idx + 1
+                );
+            }
+            Ok(cards.len())
+        }
+
+        check_generator(generate, &[10, 100, 1000], cards);
+        assert_eq!(cards(&generate(20, &mut Rng::new(0))).unwrap(), 20);
+        for seed in 0..10 {
+            let input = generate(20, &mut Rng::new(seed));
+            part1(&input).unwrap();
+            part2(&input).unwrap();
+        }
+    }
 }
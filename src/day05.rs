@@ -1,128 +1,567 @@
-use std::collections::HashMap;
+use std::{
+    collections::HashSet,
+    fmt::{Display, Write},
+};
 
-use aoc2023::Day;
-use color_eyre::Result;
+use aoc2023::{
+    graph::DiGraph,
+    interval::{Interval, IntervalSet},
+    parse,
+    rng::Rng,
+    Day,
+};
+use color_eyre::{
+    eyre::{bail, ensure, eyre, ContextCompat, WrapErr},
+    Result,
+};
+use itertools::Itertools;
 use nom::{
     bytes::complete::tag,
-    character::complete::{space1, u64},
+    character::complete::u64,
     combinator::map,
-    multi::separated_list1,
-    sequence::{separated_pair, tuple},
+    sequence::{preceded, separated_pair},
     IResult,
 };
 
 inventory::submit! {
     Day::new(5, part1, part2)
+        .with_version("2")
+        .with_generator(generate)
+        .with_tools(&[
+            ("explain", explain),
+            ("check", check),
+            ("convert", convert),
+            ("seeds-at", seeds_at),
+        ])
 }
 
 fn part1(input: &str) -> Result<String> {
     let almanach = Almanach::parse(input)?;
 
-    let min_location = almanach.part1();
+    let min_location = almanach.part1()?;
     Ok(format!("{min_location}"))
 }
 
 fn part2(input: &str) -> Result<String> {
     let almanach = Almanach::parse(input)?;
 
-    let min_location = almanach.part2();
+    let min_location = almanach.part2()?;
     Ok(format!("{min_location}"))
 }
 
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Severity {
+    /// The almanach can still be used, but may not mean what its author intended
+    Warning,
+    /// The almanach can't be used
+    Error,
+}
+
+/// A problem found in an almanach.
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Diagnostic {
+    line: usize,
+    severity: Severity,
+    message: String,
+}
+
+impl Display for Diagnostic {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let severity = match self.severity {
+            Severity::Warning => "Warning",
+            Severity::Error => "Error",
+        };
+        write!(f, "{severity} on line {}: {}", self.line, self.message)
+    }
+}
+
+#[derive(Debug, Default)]
+struct Diagnostics(Vec<Diagnostic>);
+
+impl Diagnostics {
+    fn warning(&mut self, line: usize, message: impl Into<String>) {
+        self.push(line, Severity::Warning, message.into());
+    }
+
+    fn error(&mut self, line: usize, message: impl Into<String>) {
+        self.push(line, Severity::Error, message.into());
+    }
+
+    fn push(&mut self, line: usize, severity: Severity, message: String) {
+        self.0.push(Diagnostic {
+            line,
+            severity,
+            message,
+        });
+    }
+
+    fn has_errors(&self) -> bool {
+        self.0.iter().any(|d| d.severity == Severity::Error)
+    }
+}
+
 struct Almanach {
     seeds: Vec<u64>,
-    range_maps: HashMap<String, RangeMap>,
+    /// Categories, linked by the maps converting from one to the other
+    categories: DiGraph<String, RangeMap>,
 }
 
 impl Almanach {
+    /// Parse an almanach, failing with all the errors found if there are any.
     pub fn parse(input: &str) -> Result<Self> {
-        let lines = input.lines().collect::<Vec<_>>();
-        let (_, seeds) = parse_seeds(lines[0]).unwrap();
-
-        let mut maps = HashMap::new();
-        let mut current_category: &str = "";
-        let mut current_ranges = vec![];
-
-        for line in &lines[2..] {
-            if line.trim().is_empty() {
-                maps.insert(
-                    current_category.to_string(),
-                    RangeMap::new(std::mem::take(&mut current_ranges)),
+        let (almanach, diagnostics) = Self::check(input);
+        almanach.ok_or_else(|| {
+            let errors = diagnostics
+                .iter()
+                .filter(|d| d.severity == Severity::Error)
+                .join("\n");
+            eyre!("Invalid almanach:\n{errors}")
+        })
+    }
+
+    /// Parse an almanach, reporting every problem found along the way rather than stopping at the
+    /// first one. The almanach is only returned if there are no errors.
+    pub fn check(input: &str) -> (Option<Self>, Vec<Diagnostic>) {
+        let mut diagnostics = Diagnostics::default();
+        let almanach = Self::parse_lenient(input, &mut diagnostics);
+        let almanach = almanach.filter(|_| !diagnostics.has_errors());
+        (almanach, diagnostics.0)
+    }
+
+    fn parse_lenient(input: &str, diagnostics: &mut Diagnostics) -> Option<Self> {
+        let sections = parse::sections(input);
+        let Some((seed_lines, maps)) = sections.split_first() else {
+            diagnostics.error(1, "Empty almanach");
+            return None;
+        };
+        if let Some((n, _)) = seed_lines.get(1) {
+            diagnostics.error(*n, "Expected a single line of seeds");
+        }
+        let (n, line) = seed_lines[0];
+        let seeds = match parse::parse_all(&mut parse_seeds, line) {
+            Ok(seeds) => seeds,
+            Err(e) => {
+                diagnostics.error(n, format!("Invalid seeds {line:?}: {e}"));
+                vec![]
+            }
+        };
+        if !seeds.len().is_multiple_of(2) {
+            diagnostics.warning(
+                n,
+                format!(
+                    "Odd number of seeds ({}), part 2 needs pairs of start and length",
+                    seeds.len()
+                ),
+            );
+        }
+        for chunk in seeds.chunks_exact(2) {
+            if chunk[0].checked_add(chunk[1]).is_none() {
+                diagnostics.warning(
+                    n,
+                    format!(
+                        "The seed range {}+{} overflows, part 2 can't use it",
+                        chunk[0], chunk[1]
+                    ),
                 );
-                continue;
             }
-            if let Some(category) = line.strip_suffix(" map:") {
-                current_category = category;
-                current_ranges = Vec::new();
+        }
+
+        let mut categories = DiGraph::new();
+        for lines in maps {
+            let ((n, header), range_lines) = lines.split_first().expect("sections are not empty");
+            let Some((src, dst)) = header
+                .strip_suffix(" map:")
+                .and_then(|name| name.split_once("-to-"))
+            else {
+                diagnostics.error(*n, format!("Invalid map header {header:?}"));
                 continue;
+            };
+
+            let mut ranges = vec![];
+            for (n, line) in range_lines {
+                match parse::parse_all(&mut parse_range, line) {
+                    Ok(range) => {
+                        if check_range(&range, *n, diagnostics) {
+                            ranges.push((*n, range));
+                        }
+                    }
+                    Err(e) => diagnostics.error(*n, format!("Invalid range {line:?}: {e}")),
+                }
             }
-            if let Ok((_, range)) = parse_range(line) {
-                current_ranges.push(range);
-            }
-        }
-        if !current_ranges.is_empty() {
-            maps.insert(
-                current_category.to_string(),
-                RangeMap::new(std::mem::take(&mut current_ranges)),
-            );
+            check_overlaps(&ranges, diagnostics);
+
+            let src = categories.add_node(src.to_string());
+            let dst = categories.add_node(dst.to_string());
+            let ranges = ranges.into_iter().map(|(_, range)| range).collect();
+            categories.add_edge(src, dst, RangeMap::new(ranges));
         }
-        Ok(Self {
-            seeds,
-            range_maps: maps,
-        })
+        Some(Self { seeds, categories })
     }
 
-    pub fn part1(&self) -> u64 {
+    pub fn part1(&self) -> Result<u64> {
+        let map = self.compose("seed", "location")?;
         self.seeds
             .iter()
-            .copied()
-            .map(|seed| self.seed_to_location(seed))
+            .map(|seed| map.map(*seed))
             .min()
-            .unwrap()
+            .wrap_err("No seeds")
     }
 
-    pub fn part2(&self) -> u64 {
-        self.seeds
+    pub fn part2(&self) -> Result<u64> {
+        ensure!(
+            self.seeds.len().is_multiple_of(2),
+            "Odd number of seeds, part 2 needs pairs of start and length"
+        );
+        // Rather than mapping each seed, map whole intervals of seeds: the locations are the
+        // image of the seed intervals through all the maps, and the lowest one is the start of
+        // the first interval.
+        let seeds = self
+            .seeds
             .chunks_exact(2)
             .map(|chunk| {
-                let start = chunk[0];
-                let len = chunk[1];
-                (start..start + len)
-                    .map(|seed| self.seed_to_location(seed))
-                    .min()
-                    .unwrap()
+                let end = chunk[0].checked_add(chunk[1]).wrap_err_with(|| {
+                    format!("The seed range {}+{} overflows", chunk[0], chunk[1])
+                })?;
+                Ok(Interval::new(chunk[0], end))
             })
-            .min()
-            .unwrap()
+            .collect::<Result<IntervalSet<_>>>()?;
+        let locations = self.compose("seed", "location")?.map_set(&seeds);
+        Ok(locations.intervals().first().wrap_err("No seeds")?.start)
+    }
+
+    /// Convert `v` from category `from` to category `to`.
+    pub fn convert(&self, from: &str, to: &str, v: u64) -> Result<u64> {
+        let chain = self.chain(from, to)?;
+        Ok(chain.iter().fold(v, |v, (_, map)| map.map(v)))
+    }
+
+    /// Single map converting from category `from` to category `to`.
+    pub fn compose(&self, from: &str, to: &str) -> Result<RangeMap> {
+        let chain = self.chain(from, to)?;
+        Ok(chain
+            .into_iter()
+            .fold(RangeMap::default(), |composed, (_, map)| composed.then(map)))
+    }
+
+    /// Seeds that end up at `location`.
+    pub fn seeds_at(&self, location: u64) -> Result<IntervalSet<u64>> {
+        let map = self.compose("seed", "location")?;
+        // Maps only cover values below `u64::MAX`
+        let Some(end) = location.checked_add(1) else {
+            return Ok(IntervalSet::new());
+        };
+        Ok(map.preimage(&IntervalSet::from_iter([Interval::new(location, end)])))
+    }
+
+    /// Every step of the conversion of `seed` to a location.
+    pub fn explain(&self, seed: u64) -> Result<Vec<Step<'_>>> {
+        let mut steps = vec![Step {
+            category: "seed",
+            value: seed,
+            range: None,
+        }];
+        for (category, map) in self.chain("seed", "location")? {
+            let v = steps.last().expect("there is a seed step").value;
+            let range = map.lookup(v);
+            steps.push(Step {
+                category,
+                value: range.and_then(|r| r.map(v)).unwrap_or(v),
+                range,
+            });
+        }
+        Ok(steps)
+    }
+
+    /// Maps to apply in order to convert from category `from` to category `to`.
+    ///
+    /// There must be exactly one way to go from one to the other: this fails if some category
+    /// along the way can be converted into several categories that lead to `to`, or has several
+    /// maps to the same category.
+    ///
+    /// Each map comes with the name of the category it converts to.
+    pub fn chain(&self, from: &str, to: &str) -> Result<Vec<(&str, &RangeMap)>> {
+        let category = |name: &str| {
+            self.categories
+                .node_id(&name.to_string())
+                .wrap_err_with(|| format!("Unknown category {name:?}"))
+        };
+        let (mut current, target) = (category(from)?, category(to)?);
+
+        let mut visited = HashSet::from([current]);
+        let mut chain = vec![];
+        while current != target {
+            let mut next = self
+                .categories
+                .edges(current)
+                .filter(|(dst, _)| self.categories.is_reachable(*dst, target));
+            let Some((dst, map)) = next.next() else {
+                bail!("No chain of maps from {from:?} to {to:?}");
+            };
+            if let Some((other, _)) = next.next() {
+                bail!(
+                    "Ambiguous conversion from {from:?} to {to:?}: {:?} can be converted to both \
+                     {:?} and {:?}",
+                    self.categories.label(current),
+                    self.categories.label(dst),
+                    self.categories.label(other)
+                );
+            }
+            ensure!(
+                visited.insert(dst),
+                "Ambiguous conversion from {from:?} to {to:?}: the maps loop through {:?}",
+                self.categories.label(dst)
+            );
+            chain.push((self.categories.label(dst).as_str(), map));
+            current = dst;
+        }
+        Ok(chain)
+    }
+}
+
+/// A step of the conversion of a seed.
+struct Step<'a> {
+    category: &'a str,
+    value: u64,
+    /// Range used to get to `value`, or `None` if the previous value was mapped to itself
+    range: Option<Range>,
+}
+
+/// Print the conversion of each of the given seeds (by default, all the seeds of the almanach) as
+/// a table.
+fn explain(input: &str, args: &[String]) -> Result<String> {
+    let almanach = Almanach::parse(input)?;
+    let seeds = if args.is_empty() {
+        almanach.seeds.clone()
+    } else {
+        args.iter()
+            .map(|arg| {
+                arg.parse()
+                    .wrap_err_with(|| format!("Invalid seed {arg:?}"))
+            })
+            .collect::<Result<_>>()?
+    };
+
+    let mut out = String::new();
+    for seed in seeds {
+        let steps = almanach.explain(seed)?;
+        let width = steps.iter().map(|s| s.category.len()).max().unwrap_or(0);
+        for step in steps {
+            let range = match (step.category, step.range) {
+                ("seed", _) => String::new(),
+                (_, Some(range)) => format!("range {range}"),
+                (_, None) => "identity".to_string(),
+            };
+            let line = format!("{:<width$}  {:>20}  {range}", step.category, step.value);
+            writeln!(out, "{}", line.trim_end())?;
+        }
+        out.push('\n');
     }
+    Ok(out)
+}
 
-    fn seed_to_location(&self, seed: u64) -> u64 {
-        let soil = self.range_maps["seed-to-soil"].map(seed);
-        let fertilizer = self.range_maps["soil-to-fertilizer"].map(soil);
-        let water = self.range_maps["fertilizer-to-water"].map(fertilizer);
-        let light = self.range_maps["water-to-light"].map(water);
-        let temp = self.range_maps["light-to-temperature"].map(light);
-        let humidity = self.range_maps["temperature-to-humidity"].map(temp);
+/// Convert values from one category to another: `convert <from> <to> <value>...`.
+fn convert(input: &str, args: &[String]) -> Result<String> {
+    let [from, to, values @ ..] = args else {
+        bail!("Usage: convert <from> <to> <value>...");
+    };
+    ensure!(!values.is_empty(), "Usage: convert <from> <to> <value>...");
+    let almanach = Almanach::parse(input)?;
+    let mut out = String::new();
+    for value in values {
+        let v = value
+            .parse()
+            .wrap_err_with(|| format!("Invalid value {value:?}"))?;
+        let converted = almanach.convert(from, to, v)?;
+        writeln!(out, "{from} {v} -> {to} {converted}")?;
+    }
+    Ok(out)
+}
 
-        self.range_maps["humidity-to-location"].map(humidity)
+/// Print the seeds that end up at each of the given locations: `seeds-at <location>...`.
+fn seeds_at(input: &str, args: &[String]) -> Result<String> {
+    ensure!(!args.is_empty(), "Usage: seeds-at <location>...");
+    let almanach = Almanach::parse(input)?;
+    let mut out = String::new();
+    for arg in args {
+        let location = arg
+            .parse()
+            .wrap_err_with(|| format!("Invalid location {arg:?}"))?;
+        let seeds = almanach.seeds_at(location)?;
+        let seeds = if seeds.is_empty() {
+            "none".to_string()
+        } else {
+            seeds.intervals().iter().join(", ")
+        };
+        writeln!(out, "location {location}: seeds {seeds}")?;
     }
+    Ok(out)
 }
 
+/// Print all the problems found in the almanach.
+fn check(input: &str, _args: &[String]) -> Result<String> {
+    let (_, diagnostics) = Almanach::check(input);
+    if diagnostics.is_empty() {
+        return Ok("No problems found\n".to_string());
+    }
+    Ok(diagnostics.iter().map(|d| format!("{d}\n")).collect())
+}
+
+/// Check `range`, found on line `n`, returning whether it is usable.
+fn check_range(range: &Range, n: usize, diagnostics: &mut Diagnostics) -> bool {
+    if range.length == 0 {
+        diagnostics.warning(n, "Empty range");
+    }
+    for (name, start) in [
+        ("source", range.src_start),
+        ("destination", range.dst_start),
+    ] {
+        if start.checked_add(range.length).is_none() {
+            diagnostics.error(
+                n,
+                format!("The {name} range {start}+{} overflows", range.length),
+            );
+            return false;
+        }
+    }
+    true
+}
+
+/// Report the ranges whose source overlaps the source of another range of the same map.
+fn check_overlaps(ranges: &[(usize, Range)], diagnostics: &mut Diagnostics) {
+    let mut sorted = ranges
+        .iter()
+        .filter(|(_, range)| range.length > 0)
+        .collect::<Vec<_>>();
+    sorted.sort_by_key(|(_, range)| range.src_start);
+    // Since the ranges are sorted by start, a range overlaps some previous range if and only if it
+    // overlaps the one ending the furthest
+    let mut furthest: Option<(usize, Interval<u64>)> = None;
+    for (n, range) in sorted {
+        let src = range.src();
+        if let Some((other_n, other)) = furthest {
+            if src.overlaps(&other) {
+                let ((first_n, first), (second_n, second)) = if *n < other_n {
+                    ((*n, src), (other_n, other))
+                } else {
+                    ((other_n, other), (*n, src))
+                };
+                diagnostics.warning(
+                    second_n,
+                    format!(
+                        "Source range {second} overlaps {first} on line {first_n}, which takes \
+                         precedence"
+                    ),
+                );
+            }
+        }
+        if furthest.is_none_or(|(_, other)| src.end > other.end) {
+            furthest = Some((*n, src));
+        }
+    }
+}
+
+/// Piecewise map of values, which maps values outside of the ranges to themselves.
+///
+/// The ranges are kept sorted and disjoint: where ranges of the input overlap, the values in the
+/// overlap are mapped by the range listed first.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
 struct RangeMap(Vec<Range>);
 
 impl RangeMap {
-    pub fn new(mut ranges: Vec<Range>) -> Self {
-        ranges.sort();
-        Self(ranges)
+    pub fn new(ranges: Vec<Range>) -> Self {
+        let mut covered = IntervalSet::new();
+        let mut pieces = vec![];
+        for range in ranges {
+            let uncovered = IntervalSet::from_iter([range.src()]).difference(&covered);
+            pieces.extend(
+                uncovered
+                    .intervals()
+                    .iter()
+                    .map(|part| range.restrict(*part)),
+            );
+            covered.insert(range.src());
+        }
+        pieces.sort_by_key(|r| r.src_start);
+        Self(pieces)
     }
 
     pub fn map(&self, v: u64) -> u64 {
-        let idx = self
-            .0
-            .binary_search_by_key(&v, |r| r.src_start)
-            .unwrap_or_else(|x| x.saturating_sub(1));
-        let range = self.0[idx];
-        range.map(v).unwrap_or(v)
+        self.lookup(v).and_then(|r| r.map(v)).unwrap_or(v)
+    }
+
+    /// Range mapping `v`, if any.
+    pub fn lookup(&self, v: u64) -> Option<Range> {
+        let idx = self.0.partition_point(|r| r.src_start <= v);
+        idx.checked_sub(1)
+            .map(|idx| self.0[idx])
+            .filter(|r| r.src().contains(v))
+    }
+
+    /// Image of `set`. Each interval is split at the boundaries of the ranges, and the parts that
+    /// are not covered by any range are mapped to themselves.
+    pub fn map_set(&self, set: &IntervalSet<u64>) -> IntervalSet<u64> {
+        set.intervals()
+            .iter()
+            .flat_map(|iv| self.split(*iv))
+            .map(|range| range.dst())
+            .collect()
+    }
+
+    /// Values that `self` maps into `set`. Several parts of the domain may be mapped to the same
+    /// values, in which case they are all included.
+    pub fn preimage(&self, set: &IntervalSet<u64>) -> IntervalSet<u64> {
+        self.pieces()
+            .into_iter()
+            .flat_map(|piece| {
+                set.intervals().iter().filter_map(move |iv| {
+                    let dst = piece.dst().intersection(iv);
+                    (!dst.is_empty()).then(|| {
+                        Interval::new(
+                            piece.src_start + (dst.start - piece.dst_start),
+                            piece.src_start + (dst.end - piece.dst_start),
+                        )
+                    })
+                })
+            })
+            .collect()
+    }
+
+    /// Map applying `self`, then `other`.
+    pub fn then(&self, other: &RangeMap) -> RangeMap {
+        let ranges = self
+            .pieces()
+            .into_iter()
+            .flat_map(|piece| {
+                other
+                    .split(piece.dst())
+                    .into_iter()
+                    .map(move |range| Range {
+                        src_start: piece.src_start + (range.src_start - piece.dst_start),
+                        ..range
+                    })
+            })
+            .filter(|range| range.src_start != range.dst_start)
+            .collect();
+        RangeMap::new(ranges)
+    }
+
+    /// `iv` split into ranges mapping each part the way `self` does, including identity ranges
+    /// for the parts outside of the ranges of `self`.
+    fn split(&self, iv: Interval<u64>) -> Vec<Range> {
+        let srcs = self.0.iter().map(Range::src).collect::<Vec<_>>();
+        iv.split_across(&srcs)
+            .into_iter()
+            .map(|(part, idx)| match idx {
+                Some(idx) => self.0[idx].restrict(part),
+                None => Range::identity(part),
+            })
+            .collect()
+    }
+
+    /// Ranges covering the whole domain (except `u64::MAX`, which can't be the end of an
+    /// interval).
+    fn pieces(&self) -> Vec<Range> {
+        self.split(Interval::new(0, u64::MAX))
     }
 }
 
@@ -134,32 +573,49 @@ struct Range {
 }
 
 impl Range {
-    pub fn map(&self, v: u64) -> Option<u64> {
-        if v >= self.src_start && v < self.src_start + self.length {
-            Some(self.dst_start + (v - self.src_start))
-        } else {
-            None
+    /// Range mapping the values of `iv` to themselves.
+    pub fn identity(iv: Interval<u64>) -> Self {
+        Self {
+            src_start: iv.start,
+            dst_start: iv.start,
+            length: iv.end - iv.start,
         }
     }
-}
 
-impl PartialOrd for Range {
-    fn partial_cmp(&self, other: &Self) -> Option<std::cmp::Ordering> {
-        Some(self.cmp(other))
+    /// Source values covered by this range.
+    pub fn src(&self) -> Interval<u64> {
+        Interval::new(self.src_start, self.src_start + self.length)
+    }
+
+    /// Values the source values are mapped to.
+    pub fn dst(&self) -> Interval<u64> {
+        Interval::new(self.dst_start, self.dst_start + self.length)
+    }
+
+    pub fn map(&self, v: u64) -> Option<u64> {
+        self.src()
+            .contains(v)
+            .then(|| self.dst_start + (v - self.src_start))
+    }
+
+    /// Part of this range with source values in `part`, which must be within `self.src()`.
+    pub fn restrict(&self, part: Interval<u64>) -> Self {
+        Self {
+            src_start: part.start,
+            dst_start: self.dst_start + (part.start - self.src_start),
+            length: part.end - part.start,
+        }
     }
 }
 
-impl Ord for Range {
-    fn cmp(&self, other: &Self) -> std::cmp::Ordering {
-        self.src_start.cmp(&other.src_start)
+impl Display for Range {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{} {} {}", self.dst_start, self.src_start, self.length)
     }
 }
 
 fn parse_seeds(input: &str) -> IResult<&str, Vec<u64>> {
-    map(
-        tuple((tag("seeds: "), separated_list1(space1, u64))),
-        |(_, seeds)| seeds,
-    )(input)
+    preceded(tag("seeds: "), parse::numbers(u64))(input)
 }
 
 fn parse_range(input: &str) -> IResult<&str, Range> {
@@ -173,24 +629,56 @@ fn parse_range(input: &str) -> IResult<&str, Range> {
     )(input)
 }
 
+fn generate(size: usize, rng: &mut Rng) -> String {
+    const CATEGORIES: [&str; 8] = [
+        "seed",
+        "soil",
+        "fertilizer",
+        "water",
+        "light",
+        "temperature",
+        "humidity",
+        "location",
+    ];
+    const MAX: u64 = 1 << 32;
+
+    let size = size.max(1);
+    // Seed ranges are kept short so that part 2 can be checked against brute force
+    let seeds = (0..size)
+        .map(|_| {
+            let len = rng.between(1, 1000);
+            format!("{} {len}", rng.below(MAX - len))
+        })
+        .join(" ");
+    let mut input = format!("seeds: {seeds}\n");
+
+    for (src, dst) in CATEGORIES.iter().tuple_windows() {
+        // Split the value space into `size` non-overlapping source ranges, with some gaps
+        let mut bounds = (0..2 * size).map(|_| rng.below(MAX)).collect::<Vec<_>>();
+        bounds.sort();
+        bounds.dedup();
+
+        input.push_str(&format!("\n{src}-to-{dst} map:\n"));
+        for (src_start, src_end) in bounds.into_iter().tuples() {
+            let length = src_end - src_start;
+            if length == 0 {
+                continue;
+            }
+            let dst_start = rng.below(MAX - length);
+            input.push_str(&format!("{dst_start} {src_start} {length}\n"));
+        }
+    }
+    input
+}
+
 #[cfg(test)]
 mod tests {
-    use super::*;
+    use aoc2023::rng::check_generator;
+    use color_eyre::eyre::ensure;
 
-    #[test]
-    fn test_range() {
-        let (_, range) = parse_range("60 56 37").unwrap();
-        assert_eq!(range.map(20), None);
-        assert_eq!(range.map(56), Some(60));
-        assert_eq!(range.map(57), Some(61));
-        assert_eq!(range.map(92), Some(96));
-        assert_eq!(range.map(93), None);
-    }
+    use super::*;
 
-    #[test]
-    fn test_part1_part2() {
-        let a = Almanach::parse(
-            "seeds: 79 14 55 13
+    const EXAMPLE: &str = "seeds: 79 14 55 13
 
 seed-to-soil map:
 50 98 2
@@ -222,11 +710,252 @@ temperature-to-humidity map:
 
 humidity-to-location map:
 60 56 37
-56 93 4",
+56 93 4";
+
+    #[test]
+    fn test_range() {
+        let (_, range) = parse_range("60 56 37").unwrap();
+        assert_eq!(range.map(20), None);
+        assert_eq!(range.map(56), Some(60));
+        assert_eq!(range.map(57), Some(61));
+        assert_eq!(range.map(92), Some(96));
+        assert_eq!(range.map(93), None);
+    }
+
+    #[test]
+    fn test_part1_part2() {
+        let a = Almanach::parse(EXAMPLE).unwrap();
+
+        assert_eq!(a.part1().unwrap(), 35);
+        assert_eq!(a.part2().unwrap(), 46);
+    }
+
+    #[test]
+    fn test_convert() {
+        let a = Almanach::parse(EXAMPLE).unwrap();
+        assert_eq!(a.convert("seed", "soil", 79).unwrap(), 81);
+        assert_eq!(a.convert("seed", "location", 79).unwrap(), 82);
+        assert_eq!(a.convert("soil", "soil", 79).unwrap(), 79);
+        assert_eq!(a.convert("water", "humidity", 81).unwrap(), 78);
+        assert!(a.convert("location", "seed", 82).is_err());
+        assert!(a.convert("seed", "pebble", 82).is_err());
+
+        let a = Almanach::parse(
+            "seeds: 1
+
+a-to-b map:
+1 2 3
+
+a-to-c map:
+1 2 3
+
+b-to-d map:
+1 2 3
+
+c-to-d map:
+1 2 3",
         )
         .unwrap();
+        assert_eq!(a.convert("b", "d", 2).unwrap(), 1);
+        assert_eq!(
+            a.convert("a", "d", 2).unwrap_err().to_string(),
+            "Ambiguous conversion from \"a\" to \"d\": \"a\" can be converted to both \"b\" and \"c\""
+        );
+
+        let args = ["seed", "location", "79", "14"].map(String::from);
+        assert_eq!(
+            convert(EXAMPLE, &args).unwrap(),
+            "seed 79 -> location 82\nseed 14 -> location 43\n"
+        );
+        assert!(convert(EXAMPLE, &["seed".to_string()]).is_err());
+        assert!(convert(EXAMPLE, &["seed", "soil", "x"].map(String::from)).is_err());
+        assert!(convert(EXAMPLE, &["location", "seed", "1"].map(String::from)).is_err());
+    }
+
+    #[test]
+    fn test_range_map() {
+        let map = RangeMap::new(vec![
+            parse_range("50 10 10").unwrap().1,
+            parse_range("0 15 10").unwrap().1,
+        ]);
+        // The first range wins where they overlap
+        assert_eq!(map.map(12), 52);
+        assert_eq!(map.map(19), 59);
+        assert_eq!(map.map(20), 5);
+        assert_eq!(map.map(25), 25);
+        assert_eq!(map.map(5), 5);
+
+        // Both 5 and 20 are mapped to 5
+        let set = |start, end| IntervalSet::from_iter([Interval::new(start, end)]);
+        assert_eq!(
+            map.preimage(&set(5, 6)).intervals(),
+            &[Interval::new(5, 6), Interval::new(20, 21)]
+        );
+        // 50 and 51 are outside of the ranges, so they are mapped to themselves too
+        assert_eq!(map.preimage(&set(50, 52)), set(10, 12).union(&set(50, 52)));
+        assert!(map.preimage(&set(10, 12)).is_empty());
+
+        let other = RangeMap::new(vec![parse_range("100 0 8").unwrap().1]);
+        let composed = map.then(&other);
+        for v in 0..100 {
+            assert_eq!(composed.map(v), other.map(map.map(v)), "{v}");
+        }
+    }
+
+    #[test]
+    fn test_check() {
+        let (almanach, diagnostics) = Almanach::check(
+            "seeds: 1 2 3
+
+a-to-b map:
+10 0 5
+0 3 4
+1 2
+0 20 0
+
+b to c map:
+0 0 1
+
+c-to-d map:
+0 18446744073709551615 1",
+        );
+        assert!(almanach.is_none());
+        assert_eq!(
+            diagnostics.iter().map(|d| d.to_string()).collect::<Vec<_>>(),
+            vec![
+                "Warning on line 1: Odd number of seeds (3), part 2 needs pairs of start and length",
+                "Error on line 6: Invalid range \"1 2\": Expected Tag at \"\"",
+                "Warning on line 7: Empty range",
+                "Warning on line 5: Source range [3, 7) overlaps [0, 5) on line 4, which takes \
+                 precedence",
+                "Error on line 9: Invalid map header \"b to c map:\"",
+                "Error on line 13: The source range 18446744073709551615+1 overflows",
+            ]
+        );
+        assert!(Almanach::parse("seeds: x").is_err());
+
+        let (almanach, diagnostics) = Almanach::check(EXAMPLE);
+        assert!(almanach.is_some());
+        assert!(diagnostics.is_empty());
+
+        assert_eq!(check(EXAMPLE, &[]).unwrap(), "No problems found\n");
+        assert_eq!(
+            check("seeds: 1 2 3\n\na-to-b map:\n0 0 0", &[]).unwrap(),
+            "Warning on line 1: Odd number of seeds (3), part 2 needs pairs of start and length\n\
+             Warning on line 4: Empty range\n"
+        );
+
+        let input = "seeds: 18446744073709551615 1 5 2\n\na-to-b map:\n0 0 1";
+        assert_eq!(
+            check(input, &[]).unwrap(),
+            "Warning on line 1: The seed range 18446744073709551615+1 overflows, part 2 can't use \
+             it\n"
+        );
+        let a = Almanach::parse(&input.replace("a-to-b", "seed-to-location")).unwrap();
+        assert_eq!(a.part1().unwrap(), 1);
+        assert_eq!(
+            a.part2().unwrap_err().to_string(),
+            "The seed range 18446744073709551615+1 overflows"
+        );
+    }
 
-        assert_eq!(a.part1(), 35);
-        assert_eq!(a.part2(), 46);
+    #[test]
+    fn test_explain() {
+        let a = Almanach::parse(EXAMPLE).unwrap();
+        let steps = a.explain(79).unwrap();
+        assert_eq!(
+            steps
+                .iter()
+                .map(|s| (s.category, s.value))
+                .collect::<Vec<_>>(),
+            vec![
+                ("seed", 79),
+                ("soil", 81),
+                ("fertilizer", 81),
+                ("water", 81),
+                ("light", 74),
+                ("temperature", 78),
+                ("humidity", 78),
+                ("location", 82)
+            ]
+        );
+        assert_eq!(steps[1].range.unwrap().to_string(), "52 50 48");
+        assert_eq!(steps[2].range, None);
+        assert!(explain(EXAMPLE, &["79".to_string()])
+            .unwrap()
+            .starts_with("seed                           79\nsoil                           81  range 52 50 48\n"));
+    }
+
+    #[test]
+    fn test_compose() {
+        let a = Almanach::parse(EXAMPLE).unwrap();
+        let map = a.compose("seed", "location").unwrap();
+        for seed in 0..200 {
+            assert_eq!(map.map(seed), a.convert("seed", "location", seed).unwrap());
+        }
+        assert!(a.seeds_at(46).unwrap().contains(82));
+        assert!(a.seeds_at(35).unwrap().contains(13));
+        assert!(a.seeds_at(u64::MAX).unwrap().is_empty());
+
+        let args = ["46", "35"].map(String::from);
+        assert_eq!(
+            seeds_at(EXAMPLE, &args).unwrap(),
+            "location 46: seeds [82, 83)\nlocation 35: seeds [13, 14)\n"
+        );
+        assert!(seeds_at(EXAMPLE, &[]).is_err());
+    }
+
+    #[test]
+    fn test_part2_brute_force() {
+        for seed in 0..10 {
+            let a = Almanach::parse(&generate(5, &mut Rng::new(seed))).unwrap();
+            let brute_force = a
+                .seeds
+                .chunks_exact(2)
+                .flat_map(|chunk| chunk[0]..chunk[0] + chunk[1])
+                .map(|seed| a.convert("seed", "location", seed).unwrap())
+                .min()
+                .unwrap();
+            assert_eq!(a.part2().unwrap(), brute_force);
+
+            // Check from the other direction: some seed lands on the lowest location, and none
+            // on a lower one
+            let seeds = a
+                .seeds
+                .chunks_exact(2)
+                .map(|chunk| Interval::new(chunk[0], chunk[0] + chunk[1]))
+                .collect::<IntervalSet<_>>();
+            let map = a.compose("seed", "location").unwrap();
+            let lower = IntervalSet::from_iter([Interval::new(0, brute_force)]);
+            assert!(map.preimage(&lower).intersection(&seeds).is_empty());
+            assert!(!a
+                .seeds_at(brute_force)
+                .unwrap()
+                .intersection(&seeds)
+                .is_empty());
+        }
+    }
+
+    #[test]
+    fn test_generate() {
+        fn seed_ranges(input: &str) -> Result<usize> {
+            let (almanach, diagnostics) = Almanach::check(input);
+            ensure!(
+                diagnostics.is_empty(),
+                "Unexpected diagnostics: {}",
+                diagnostics.iter().join("; ")
+            );
+            let almanach = almanach.wrap_err("No almanach")?;
+            almanach.chain("seed", "location")?;
+            Ok(almanach.seeds.len() / 2)
+        }
+
+        check_generator(generate, &[5, 50, 500], seed_ranges);
+        assert_eq!(seed_ranges(&generate(20, &mut Rng::new(0))).unwrap(), 20);
+        for seed in 0..10 {
+            let input = generate(5, &mut Rng::new(seed));
+            part1(&input).unwrap();
+            part2(&input).unwrap();
+        }
     }
 }
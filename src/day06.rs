@@ -1,15 +1,21 @@
-use aoc2023::Day;
-use color_eyre::{eyre::Context, Result};
-use regex::Regex;
+use std::{fmt::Write, ops::RangeInclusive};
+
+use aoc2023::{rng::Rng, Day};
+use color_eyre::{
+    eyre::{ensure, Context, ContextCompat},
+    Result,
+};
 
 inventory::submit! {
     Day::new(6, part1, part2)
+        .with_generator(generate)
+        .with_tools(&[("table", table)])
 }
 
 fn part1(input: &str) -> Result<String> {
     let races = parse_races(input)?;
 
-    let result: usize = races.into_iter().map(|r| r.num_record_beating()).product();
+    let result: u128 = races.into_iter().map(|r| r.num_record_beating()).product();
 
     Ok(format!("{result}"))
 }
@@ -17,68 +23,413 @@ fn part1(input: &str) -> Result<String> {
 fn part2(input: &str) -> Result<String> {
     let race = parse_single_races(input)?;
 
-    let result: usize = race.num_record_beating();
+    let result = race.num_record_beating();
 
     Ok(format!("{result}"))
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub struct Race {
-    time: u64,
-    dist: u64,
+    time: u128,
+    dist: u128,
 }
 
 impl Race {
-    pub fn new(time: u64, dist: u64) -> Self {
+    pub fn new(time: u128, dist: u128) -> Self {
         Self { time, dist }
     }
 
-    pub fn num_record_beating(&self) -> usize {
-        (0..self.time)
-            .map(|t| {
-                let remaining_t = self.time - t;
-                let speed = t;
+    /// Whether holding the button for `hold` milliseconds beats the record.
+    pub fn beats(&self, hold: u128) -> bool {
+        // hold * (time - hold) > dist, without overflowing
+        hold < self.time && hold > self.dist / (self.time - hold)
+    }
+
+    /// Number of hold times beating the record.
+    ///
+    /// The distance `hold * (time - hold)` is symmetric around `time / 2`, so the winning hold
+    /// times are `first..=time - first`, where `first` is the smallest one.
+    pub fn num_record_beating(&self) -> u128 {
+        self.winning_holds()
+            .map_or(0, |holds| holds.end() - holds.start() + 1)
+    }
+
+    /// Distance travelled when holding the button for `hold` milliseconds, or `None` if the race
+    /// is over by then or the distance doesn't fit in a `u128`.
+    pub fn distance(&self, hold: u128) -> Option<u128> {
+        hold.checked_mul(self.time.checked_sub(hold)?)
+    }
+
+    /// Distance travelled past the record (negative if the record isn't beaten) when holding the
+    /// button for `hold` milliseconds, or `None` if it doesn't fit in an `i128`.
+    pub fn margin(&self, hold: u128) -> Option<i128> {
+        let distance = i128::try_from(self.distance(hold)?).ok()?;
+        distance.checked_sub(i128::try_from(self.dist).ok()?)
+    }
+
+    /// Hold times travelling the furthest: half of the race, which is two hold times for races
+    /// lasting an odd number of milliseconds.
+    pub fn optimal_holds(&self) -> RangeInclusive<u128> {
+        self.time / 2..=self.time.div_ceil(2)
+    }
+
+    /// Furthest distance that can be travelled, if it fits in a `u128`.
+    pub fn max_distance(&self) -> Option<u128> {
+        self.distance(self.time / 2)
+    }
+
+    /// All the hold times beating the record, if any.
+    pub fn winning_holds(&self) -> Option<RangeInclusive<u128>> {
+        let first = self.first_winning_hold()?;
+        Some(first..=self.time - first)
+    }
+
+    /// Smallest hold time beating the record, if any.
+    fn first_winning_hold(&self) -> Option<u128> {
+        // The distance is the largest for half of the race
+        let mid = self.time / 2;
+        if !self.beats(mid) {
+            return None;
+        }
+
+        // Winning hold times are between the roots of hold² - time * hold + dist = 0. Since the
+        // record can be beaten, the discriminant is positive.
+        let disc = self
+            .time
+            .checked_mul(self.time)
+            .zip(self.dist.checked_mul(4))
+            .map(|(time2, dist4)| time2 - dist4);
+        let Some(disc) = disc else {
+            // The race is too long to square its time, fall back to a binary search
+            let (mut lo, mut hi) = (0, mid);
+            while lo < hi {
+                let hold = lo + (hi - lo) / 2;
+                if self.beats(hold) {
+                    hi = hold;
+                } else {
+                    lo = hold + 1;
+                }
+            }
+            return Some(lo);
+        };
 
-                speed * remaining_t
-            })
-            .filter(|d| *d > self.dist)
-            .count()
+        // The square root is rounded down, so the estimate is at most one off. Ties with the
+        // record don't count, which the checks below take care of.
+        let mut first = (self.time - disc.isqrt()) / 2;
+        while first > 0 && self.beats(first - 1) {
+            first -= 1;
+        }
+        while !self.beats(first) {
+            first += 1;
+        }
+        Some(first)
     }
 }
 
 fn parse_races(input: &str) -> Result<Vec<Race>> {
-    let lines = input.lines().collect::<Vec<_>>();
-    let re = Regex::new(r"(\d+)").unwrap();
-    let times = re
-        .find_iter(lines[0])
-        .map(|m| m.as_str().parse::<u64>().wrap_err("invalid number"))
-        .collect::<Result<Vec<u64>>>()?;
-    let distances = re
-        .find_iter(lines[1])
-        .map(|m| m.as_str().parse::<u64>().wrap_err("invalid number"))
-        .collect::<Result<Vec<u64>>>()?;
-
-    Ok(times
+    let (times, dists) = parse_lines(input)?;
+    ensure!(
+        times.len() == dists.len(),
+        "Got {} times but {} distances",
+        times.len(),
+        dists.len()
+    );
+    times
         .into_iter()
-        .zip(distances)
-        .map(|(t, d)| Race::new(t, d))
-        .collect())
+        .zip(dists)
+        .map(|(time, dist)| Ok(Race::new(parse_number(time)?, parse_number(dist)?)))
+        .collect()
 }
 
+/// Parse the input as a single race, ignoring the spaces between digits.
 fn parse_single_races(input: &str) -> Result<Race> {
-    let lines = input.lines().collect::<Vec<_>>();
-    let re = Regex::new(r"(\d+)").unwrap();
-    let time_str = re
-        .find_iter(lines[0])
-        .map(|m| m.as_str())
-        .collect::<String>();
-    let distance_str = re
-        .find_iter(lines[1])
-        .map(|m| m.as_str())
-        .collect::<String>();
-
+    let (times, dists) = parse_lines(input)?;
     Ok(Race::new(
-        time_str.parse::<u64>()?,
-        distance_str.parse::<u64>()?,
+        parse_number(&times.concat())?,
+        parse_number(&dists.concat())?,
     ))
 }
+
+/// Values of the `Time:` and `Distance:` lines.
+fn parse_lines(input: &str) -> Result<(Vec<&str>, Vec<&str>)> {
+    let mut lines = input.lines();
+    let mut values = |name: &str| -> Result<Vec<&str>> {
+        let line = lines
+            .next()
+            .wrap_err_with(|| format!("Missing {name} line"))?;
+        let values = line
+            .strip_prefix(name)
+            .and_then(|rest| rest.strip_prefix(':'))
+            .wrap_err_with(|| format!("Expected a {name} line, got {line:?}"))?;
+        Ok(values.split_whitespace().collect())
+    };
+    Ok((values("Time")?, values("Distance")?))
+}
+
+fn parse_number(s: &str) -> Result<u128> {
+    s.parse().wrap_err_with(|| format!("Invalid number {s:?}"))
+}
+
+/// Print the strategy for each race, and for the whole input read as a single race.
+fn table(input: &str, _args: &[String]) -> Result<String> {
+    let races = parse_races(input)?;
+    let single = parse_single_races(input)?;
+
+    let fmt_holds = |holds: RangeInclusive<u128>| {
+        if holds.start() == holds.end() {
+            holds.start().to_string()
+        } else {
+            format!("{}-{}", holds.start(), holds.end())
+        }
+    };
+    let fmt_option = |v: Option<String>| v.unwrap_or_else(|| "-".to_string());
+
+    let mut rows = vec![[
+        "Race",
+        "Time",
+        "Record",
+        "Winning holds",
+        "Ways to win",
+        "Optimal hold",
+        "Max distance",
+        "Margin",
+    ]
+    .map(String::from)];
+    let named = races
+        .iter()
+        .enumerate()
+        .map(|(idx, race)| ((idx + 1).to_string(), race))
+        .chain([("All".to_string(), &single)]);
+    for (name, race) in named {
+        rows.push([
+            name,
+            race.time.to_string(),
+            race.dist.to_string(),
+            fmt_option(race.winning_holds().map(fmt_holds)),
+            race.num_record_beating().to_string(),
+            fmt_holds(race.optimal_holds()),
+            fmt_option(race.max_distance().map(|d| d.to_string())),
+            fmt_option(race.margin(race.time / 2).map(|m| format!("{m:+}"))),
+        ]);
+    }
+
+    let widths = (0..rows[0].len())
+        .map(|col| rows.iter().map(|row| row[col].len()).max().unwrap_or(0))
+        .collect::<Vec<_>>();
+    let mut out = String::new();
+    for row in rows {
+        let cells = row
+            .iter()
+            .zip(&widths)
+            .map(|(cell, width)| format!("{cell:>width$}"))
+            .collect::<Vec<_>>();
+        writeln!(out, "{}", cells.join("  "))?;
+    }
+    Ok(out)
+}
+
+/// Generates races lasting up to `size` milliseconds.
+fn generate(size: usize, rng: &mut Rng) -> String {
+    let max_time = (size as u64).max(2);
+    // Part 2 concatenates all the numbers, so use fewer races for long ones to keep the
+    // concatenated record distance within a `u64`.
+    let digits = max_time.ilog10() as usize + 1;
+    let num_races = (19 / (2 * digits)).clamp(1, 4);
+
+    let races = (0..num_races)
+        .map(|_| {
+            let time = rng.between(max_time / 2 + 1, max_time);
+            // Make sure the record can be beaten
+            let hold = rng.between(1, time / 2);
+            Race::new(time as u128, (hold * (time - hold) - 1) as u128)
+        })
+        .collect::<Vec<_>>();
+
+    let width = races
+        .iter()
+        .map(|r| r.dist.to_string().len())
+        .max()
+        .unwrap_or_default();
+    let times = races
+        .iter()
+        .map(|r| format!("{:>width$}", r.time))
+        .collect::<Vec<_>>()
+        .join(" ");
+    let dists = races
+        .iter()
+        .map(|r| format!("{:>width$}", r.dist))
+        .collect::<Vec<_>>()
+        .join(" ");
+    format!("Time:     {times}\nDistance: {dists}\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use aoc2023::rng::check_generator;
+    use color_eyre::eyre::ensure;
+
+    use super::*;
+
+    const EXAMPLE: &str = "Time:      7  15   30
+Distance:  9  40  200";
+
+    fn num_record_beating_brute_force(race: &Race) -> u128 {
+        (0..race.time)
+            .filter(|hold| hold * (race.time - hold) > race.dist)
+            .count() as u128
+    }
+
+    #[test]
+    fn test_part1() {
+        assert_eq!(part1(EXAMPLE).unwrap(), "288");
+    }
+
+    #[test]
+    fn test_part2() {
+        assert_eq!(part2(EXAMPLE).unwrap(), "71503");
+    }
+
+    #[test]
+    fn test_strategy() {
+        let race = Race::new(7, 9);
+        assert_eq!(race.optimal_holds(), 3..=4);
+        assert_eq!(race.max_distance(), Some(12));
+        assert_eq!(race.winning_holds(), Some(2..=5));
+        assert_eq!(race.distance(2), Some(10));
+        assert_eq!(race.distance(8), None);
+        assert_eq!(race.margin(3), Some(3));
+        assert_eq!(race.margin(1), Some(-3));
+        assert_eq!(race.margin(0), Some(-9));
+
+        let race = Race::new(30, 200);
+        assert_eq!(race.optimal_holds(), 15..=15);
+        assert_eq!(race.winning_holds(), Some(11..=19));
+        assert_eq!(Race::new(30, 225).winning_holds(), None);
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        assert!(parse_races("Time: 7 15").is_err());
+        assert!(parse_races("Time: 7 15\nDistance: 9").is_err());
+        assert!(parse_races("Time: 7\nDist: 9").is_err());
+        assert!(parse_single_races("Time: 7 x\nDistance: 9").is_err());
+        assert!(parse_single_races("").is_err());
+    }
+
+    #[test]
+    fn test_table() {
+        assert_eq!(
+            table(EXAMPLE, &[]).unwrap(),
+            "\
+Race   Time  Record  Winning holds  Ways to win  Optimal hold  Max distance       Margin
+   1      7       9            2-5            4           3-4            12           +3
+   2     15      40           4-11            8           7-8            56          +16
+   3     30     200          11-19            9            15           225          +25
+ All  71530  940200       14-71516        71503         35765    1279135225  +1278195025
+"
+        );
+    }
+
+    #[test]
+    fn test_random_races_against_brute_force() {
+        let mut rng = Rng::new(0);
+        for _ in 0..10_000 {
+            let time = rng.below(200) as u128;
+            // Around the maximum distance, to hit the cases where the record can't be beaten
+            let dist = rng.below((time * time / 4 + 5) as u64) as u128;
+            let race = Race::new(time, dist);
+            assert_eq!(
+                race.num_record_beating(),
+                num_record_beating_brute_force(&race),
+                "{race:?}"
+            );
+        }
+
+        // Ties with the record don't count
+        assert_eq!(Race::new(10, 25).num_record_beating(), 0);
+        assert_eq!(Race::new(10, 24).num_record_beating(), 1);
+        assert_eq!(Race::new(10, 21).num_record_beating(), 3);
+    }
+
+    #[test]
+    fn test_random_large_races() {
+        let mut rng = Rng::new(0);
+        for time in [1 << 40, (1 << 63) + 1, 1 << 64, 1 << 100, u128::MAX] {
+            // Keep the record within a `u128`
+            let max_hold = (time / 2).min(u128::MAX / time);
+            for _ in 0..100 {
+                let hold = rng.next_u64() as u128 % max_hold;
+                let race = Race::new(time, hold * (time - hold));
+                // Exactly `hold` and `time - hold` tie with the record
+                assert_eq!(race.num_record_beating(), time - 2 * hold - 1, "{race:?}");
+            }
+        }
+    }
+
+    #[test]
+    fn test_exact_square_discriminant() {
+        // A record of `root * (time - root)` makes the discriminant `(time - 2 * root)²`, so the
+        // square root is exact and both roots tie with the record
+        for time in 0..300u128 {
+            for root in 0..=time / 2 {
+                let race = Race::new(time, root * (time - root));
+                let expected = (time - 2 * root).saturating_sub(1);
+                assert_eq!(race.num_record_beating(), expected, "{race:?}");
+                assert_eq!(expected, num_record_beating_brute_force(&race), "{race:?}");
+            }
+        }
+    }
+
+    #[test]
+    fn test_record_at_max_distance() {
+        for time in 0..300u128 {
+            let max = Race::new(time, 0).max_distance().unwrap();
+            let race = Race::new(time, max);
+            assert_eq!(race.winning_holds(), None, "{race:?}");
+            assert_eq!(race.num_record_beating(), 0, "{race:?}");
+            if max > 0 {
+                // Only the optimal holds beat a record just below the maximum
+                let race = Race::new(time, max - 1);
+                assert_eq!(race.winning_holds(), Some(race.optimal_holds()), "{race:?}");
+            }
+        }
+    }
+
+    #[test]
+    fn test_near_u64_max() {
+        for time in [u64::MAX as u128 - 2, u64::MAX as u128 - 1, u64::MAX as u128] {
+            // The square of the time fits in a `u128`, so this goes through `isqrt`
+            assert!(time.checked_mul(time).is_some());
+            for hold in [1, 2, 1 << 32, time / 3, time / 2 - 1, time / 2] {
+                let dist = hold * (time - hold);
+                let race = Race::new(time, dist);
+                let expected = (time - 2 * hold).saturating_sub(1);
+                assert_eq!(race.num_record_beating(), expected, "{race:?}");
+                let race = Race::new(time, dist - 1);
+                assert_eq!(race.num_record_beating(), time - 2 * hold + 1, "{race:?}");
+                assert_eq!(race.winning_holds().unwrap().start(), &hold, "{race:?}");
+            }
+        }
+    }
+
+    #[test]
+    fn test_generate() {
+        fn longest_race(input: &str) -> Result<usize> {
+            let races = parse_races(input)?;
+            parse_single_races(input)?;
+            for race in &races {
+                ensure!(race.num_record_beating() > 0, "{race:?} can't be won");
+            }
+            let longest = races.iter().map(|r| r.time).max().wrap_err("No races")?;
+            Ok(longest as usize)
+        }
+
+        check_generator(generate, &[10, 100, 1000, 100_000], longest_race);
+        for seed in 0..10 {
+            let input = generate(100, &mut Rng::new(seed));
+            assert!(longest_race(&input).unwrap() <= 100);
+            part1(&input).unwrap();
+            part2(&input).unwrap();
+        }
+    }
+}
@@ -1,112 +1,150 @@
 use std::{
-    cmp::{Ordering, Reverse},
+    cmp::Reverse,
     collections::HashMap,
-    sync::Mutex,
+    fmt::{Display, Write},
+    hash::Hash,
+    io::BufRead,
 };
 
-use aoc2023::Day;
+use aoc2023::{rng::Rng, Day};
 use color_eyre::{
-    eyre::{bail, ContextCompat},
+    eyre::{bail, ensure, ContextCompat, WrapErr},
     Result,
 };
 use itertools::Itertools;
-use once_cell::sync::Lazy;
 
 inventory::submit! {
     Day::new(7, part1, part2)
+        .with_streaming(part1_stream, part2_stream)
+        .with_generator(generate)
+        .with_tools(&[("winnings", winnings), ("poker", poker)])
 }
 
 fn part1(input: &str) -> Result<String> {
-    let mut bids = input.lines().map(parse_bid).collect::<Result<Vec<_>>>()?;
-    bids.sort_by_key(|bid| bid.0);
-    let winnings: u64 = bids
-        .into_iter()
-        .enumerate()
-        .map(|(idx, bid)| {
-            let rank = (idx + 1) as u64;
-            bid.1 * rank
-        })
-        .sum();
-    Ok(format!("{winnings}"))
+    part1_stream(&mut input.as_bytes())
 }
 
 fn part2(input: &str) -> Result<String> {
-    let mut bids = input.lines().map(parse_bid).collect::<Result<Vec<_>>>()?;
-    bids.sort_by(|bid1, bid2| cmp_part2(&bid1.0, &bid2.0));
-    let winnings: u64 = bids
-        .into_iter()
-        .enumerate()
-        .map(|(idx, bid)| {
-            let rank = (idx + 1) as u64;
-            bid.1 * rank
-        })
-        .sum();
+    part2_stream(&mut input.as_bytes())
+}
+
+fn part1_stream(input: &mut dyn BufRead) -> Result<String> {
+    let winnings = total_winnings(input, &Rules::part1())?;
     Ok(format!("{winnings}"))
 }
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
-pub enum Card {
-    C2,
-    C3,
-    C4,
-    C5,
-    C6,
-    C7,
-    C8,
-    C9,
-    T,
-    J,
-    Q,
-    K,
-    A,
+fn part2_stream(input: &mut dyn BufRead) -> Result<String> {
+    let winnings = total_winnings(input, &Rules::part2())?;
+    Ok(format!("{winnings}"))
 }
 
-impl Card {
-    pub fn non_joker() -> [Self; 12] {
-        [
-            Self::C2,
-            Self::C3,
-            Self::C4,
-            Self::C5,
-            Self::C6,
-            Self::C7,
-            Self::C8,
-            Self::C9,
-            Self::T,
-            Self::Q,
-            Self::K,
-            Self::A,
-        ]
+/// Total winnings under custom rules, given as `key=value` arguments on top of the part 1 rules:
+/// `order` (cards from weakest to strongest), `wild` (cards standing for any other card), `size`
+/// (number of cards in a hand) and `tie-break` (`in-order` or `sorted`).
+///
+/// For instance `wild=J order=J23456789TQKA` gives the part 2 rules. `rules=poker` ranks the bids
+/// as poker hands like `AsKdQh2c2h` instead.
+fn winnings(input: &str, args: &[String]) -> Result<String> {
+    let mut order = Rules::part1().order.into_iter().collect::<String>();
+    let mut wild = String::new();
+    let mut hand_size = 5;
+    let mut tie_break = TieBreak::InOrder;
+    let mut poker = false;
+    for arg in args {
+        let (key, value) = arg
+            .split_once('=')
+            .wrap_err_with(|| format!("Invalid argument {arg:?}, expected key=value"))?;
+        match key {
+            "rules" => {
+                poker = match value {
+                    "camel" => false,
+                    "poker" => true,
+                    _ => bail!("Invalid rules {value:?} (expected camel or poker)"),
+                }
+            }
+            "order" => order = value.to_string(),
+            "wild" => wild = value.to_string(),
+            "size" => {
+                hand_size = value
+                    .parse()
+                    .wrap_err_with(|| format!("Invalid hand size {value:?}"))?
+            }
+            "tie-break" => tie_break = value.parse()?,
+            _ => bail!("Unknown rule {key:?} (available: rules, order, wild, size, tie-break)"),
+        }
     }
+    let winnings = if poker {
+        ensure!(
+            args.len() == 1,
+            "Poker rules can't be customized, only rules=poker is allowed"
+        );
+        total_winnings(&mut input.as_bytes(), &Poker)?
+    } else {
+        let rules = Rules::new(&order)?
+            .with_wild(&wild)?
+            .with_hand_size(hand_size)?
+            .with_tie_break(tie_break);
+        total_winnings(&mut input.as_bytes(), &rules)?
+    };
+    Ok(winnings.to_string())
 }
 
-/// Card wrapper to change ordering for part 2
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
-pub struct Part2(Card);
-
-impl PartialOrd for Part2 {
-    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
-        Some(self.0.cmp(&other.0))
+/// Rank the poker hands given as arguments, from weakest to strongest.
+fn poker(_input: &str, args: &[String]) -> Result<String> {
+    let hands = args
+        .iter()
+        .map(|arg| Poker.parse_hand(arg))
+        .collect::<Result<Vec<_>>>()?;
+    let mut out = String::new();
+    for (rank, hand) in hands
+        .iter()
+        .sorted_by_cached_key(|h| Poker.rank(h))
+        .enumerate()
+    {
+        writeln!(out, "{:>3} {hand} {:?}", rank + 1, Poker.kind(hand)).expect("infallible");
     }
+    Ok(out)
 }
-impl Ord for Part2 {
-    fn cmp(&self, b: &Self) -> Ordering {
-        if self.0 == Card::J {
-            if b.0 == Card::J {
-                Ordering::Equal
-            } else {
-                Ordering::Less
-            }
-        } else if b.0 == Card::J {
-            Ordering::Greater
-        } else {
-            self.0.cmp(&b.0)
-        }
+
+/// Bids placed on hands that rank the same, like identical hands, or poker hands that only differ
+/// by their suits.
+///
+/// Such hands get consecutive ranks, in the order they appear in the input, so we only need to
+/// remember how many bids there were, their sum, and their sum weighted by the order they
+/// appeared in. This way ranking needs memory proportional to the number of hands that rank
+/// differently under the rules (at most the number of cards to the power of the hand size) rather
+/// than to the number of bids.
+#[derive(Debug, Default, Clone, Copy)]
+struct HandBids {
+    count: u64,
+    sum: u64,
+    weighted_sum: u64,
+}
+
+fn total_winnings<R: Ruleset>(input: &mut dyn BufRead, rules: &R) -> Result<u64> {
+    let mut bids_by_key: HashMap<R::Key, HandBids> = HashMap::new();
+    for line in input.lines() {
+        let bid = rules.parse_bid(&line?)?;
+        let bids = bids_by_key.entry(rules.key(&bid.0)?).or_default();
+        bids.weighted_sum += bids.count * bid.1;
+        bids.count += 1;
+        bids.sum += bid.1;
     }
+
+    let mut hands = bids_by_key.into_iter().collect::<Vec<_>>();
+    hands.sort_unstable_by(|(key1, _), (key2, _)| key1.cmp(key2));
+
+    let mut winnings = 0;
+    let mut rank = 1;
+    for (_key, bids) in hands {
+        winnings += rank * bids.sum + bids.weighted_sum;
+        rank += bids.count;
+    }
+    Ok(winnings)
 }
 
 #[allow(clippy::enum_variant_names)]
-#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
 pub enum Kind {
     HighCard,
     OnePair,
@@ -117,189 +155,607 @@ pub enum Kind {
     FiveOfAKind,
 }
 
-#[derive(Debug, Default)]
-pub struct Cache(Mutex<HashMap<Hand, Kind>>);
-
-impl Cache {
-    pub fn max_kind(&self, hand: Hand) -> Kind {
-        let mut map = self.0.lock().unwrap();
-        *(map
-            .entry(hand)
-            .or_insert_with_key(|hand| hand.compute_max_kind()))
+impl Kind {
+    /// Kind of a 5-card hand given how many times each card appears in it, most common first.
+    fn from_counts(counts: &[usize]) -> Self {
+        debug_assert_eq!(counts.iter().sum::<usize>(), 5, "{counts:?}");
+        match counts {
+            [5, ..] => Kind::FiveOfAKind,
+            [4, ..] => Kind::FourOfAKind,
+            [3, 2, ..] => Kind::FullHouse,
+            [3, ..] => Kind::ThreeOfAKind,
+            [2, 2, ..] => Kind::TwoPair,
+            [2, ..] => Kind::OnePair,
+            _ => Kind::HighCard,
+        }
     }
 }
 
-static CACHE: Lazy<Cache> = Lazy::new(Cache::default);
+/// How to order hands of the same kind.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TieBreak {
+    /// Compare the cards one by one, in the order they were dealt.
+    InOrder,
+    /// Compare the strongest cards of each hand first, then the next strongest, and so on.
+    Sorted,
+}
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
-pub struct Hand([Card; 5]);
+impl std::str::FromStr for TieBreak {
+    type Err = color_eyre::Report;
 
-impl Hand {
-    pub fn new(cards: [Card; 5]) -> Self {
-        Self(cards)
+    fn from_str(s: &str) -> Result<Self> {
+        match s {
+            "in-order" => Ok(Self::InOrder),
+            "sorted" => Ok(Self::Sorted),
+            _ => bail!("Invalid tie-break {s:?} (expected in-order or sorted)"),
+        }
     }
+}
 
-    pub fn kind(&self) -> Kind {
-        let counts = self.0.into_iter().counts();
+/// Cards of a hand, in the order they were dealt.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Hand(Vec<char>);
 
-        let mut counts_list = counts.into_iter().collect::<Vec<_>>();
-        counts_list.sort_by_key(|(_c, cnt)| Reverse(*cnt));
+impl Display for Hand {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.0.iter().collect::<String>())
+    }
+}
 
-        if counts_list[0].1 == 5 {
-            Kind::FiveOfAKind
-        } else if counts_list[0].1 == 4 {
-            Kind::FourOfAKind
-        } else if counts_list[0].1 == 3 {
-            if counts_list[1].1 == 2 {
-                Kind::FullHouse
-            } else {
-                Kind::ThreeOfAKind
-            }
-        } else if counts_list[0].1 == 2 {
-            if counts_list[1].1 == 2 {
-                Kind::TwoPair
-            } else {
-                Kind::OnePair
-            }
-        } else {
-            Kind::HighCard
+/// Rules of a game of Camel Cards: which cards there are and how strong they are, which ones are
+/// wild, how many cards make a hand, and how to break ties between hands of the same kind.
+#[derive(Debug, Clone)]
+pub struct Rules {
+    /// Cards from weakest to strongest
+    order: Vec<char>,
+    /// Cards that count as whichever card makes the best kind
+    wild: Vec<char>,
+    hand_size: usize,
+    tie_break: TieBreak,
+}
+
+impl Rules {
+    /// Rules with the given cards, from weakest to strongest, no wild cards, 5-card hands and
+    /// ties broken in order.
+    pub fn new(order: &str) -> Result<Self> {
+        let order = order.chars().collect::<Vec<_>>();
+        ensure!(!order.is_empty(), "There must be at least one card");
+        if let Some(c) = order.iter().duplicates().next() {
+            bail!("Card {c:?} appears several times in the card order");
         }
+        if let Some(c) = order.iter().find(|c| c.is_whitespace()) {
+            bail!("Invalid card {c:?}");
+        }
+        Ok(Self {
+            order,
+            wild: vec![],
+            hand_size: 5,
+            tie_break: TieBreak::InOrder,
+        })
     }
 
-    pub fn expand_jokers(&self) -> Vec<Hand> {
-        if let Some(idx) = self.0.iter().position(|c| *c == Card::J) {
-            Card::non_joker()
-                .into_iter()
-                .flat_map(|c| {
-                    let mut new_hand = self.0;
-                    new_hand[idx] = c;
-                    let new_hand = Hand(new_hand);
-                    new_hand.expand_jokers().into_iter()
-                })
-                .collect::<Vec<_>>()
-        } else {
-            vec![*self]
+    pub fn with_wild(mut self, wild: &str) -> Result<Self> {
+        for c in wild.chars() {
+            ensure!(self.order.contains(&c), "Unknown wild card {c:?}");
         }
+        self.wild = wild.chars().collect();
+        Ok(self)
+    }
+
+    pub fn with_hand_size(mut self, hand_size: usize) -> Result<Self> {
+        ensure!(hand_size > 0, "Hands must have at least one card");
+        self.hand_size = hand_size;
+        Ok(self)
+    }
+
+    pub fn with_tie_break(mut self, tie_break: TieBreak) -> Self {
+        self.tie_break = tie_break;
+        self
+    }
+
+    pub fn part1() -> Self {
+        Self::new("23456789TJQKA").expect("valid rules")
+    }
+
+    /// Jokers are wild, but are the weakest card when breaking ties.
+    pub fn part2() -> Self {
+        Self::new("J23456789TQKA")
+            .and_then(|rules| rules.with_wild("J"))
+            .expect("valid rules")
     }
 
-    pub fn compute_max_kind(&self) -> Kind {
-        self.expand_jokers()
+    /// How many times each card appears in `hand`, most common first, with the wild cards
+    /// standing for whichever card makes the best hand.
+    ///
+    /// Hands of any size are ranked by comparing their shapes lexicographically: the more of the
+    /// same card, the better, then the more of the next most common card, and so on. For 5 cards
+    /// this is the order of `Kind`: e.g. `[3, 2]` (full house) beats `[3, 1, 1]` (three of a
+    /// kind), which beats `[2, 2, 1]` (two pair).
+    ///
+    /// Adding the wild cards to the most common other card always gives the best shape: it turns
+    /// a pair into three or four of a kind rather than two pair, and three of a kind into four of
+    /// a kind rather than a full house.
+    pub fn shape(&self, hand: &Hand) -> Vec<usize> {
+        let (wild, others): (Vec<char>, Vec<char>) =
+            hand.0.iter().partition(|c| self.wild.contains(c));
+        let mut counts = others
             .into_iter()
-            .map(|h| h.kind())
-            .max()
-            .unwrap()
+            .counts()
+            .into_values()
+            .collect::<Vec<_>>();
+        counts.sort_by_key(|cnt| Reverse(*cnt));
+        match counts.first_mut() {
+            Some(cnt) => *cnt += wild.len(),
+            None => counts.push(wild.len()),
+        }
+        counts
     }
-    pub fn max_kind(&self) -> Kind {
-        CACHE.max_kind(*self)
+
+    fn strength(&self, card: char) -> Result<usize> {
+        self.order
+            .iter()
+            .position(|c| *c == card)
+            .wrap_err_with(|| format!("Card {card:?} is not part of these rules"))
     }
 }
 
-impl PartialOrd for Hand {
-    fn partial_cmp(&self, other: &Self) -> Option<std::cmp::Ordering> {
-        Some(self.cmp(other))
+impl Ruleset for Rules {
+    type Hand = Hand;
+    /// Shape of the hand, then the strengths of its cards.
+    type Key = (Vec<usize>, Vec<usize>);
+
+    fn parse_hand(&self, input: &str) -> Result<Hand> {
+        let hand = input
+            .chars()
+            .map(|c| {
+                ensure!(self.order.contains(&c), "Invalid card {c:?}");
+                Ok(c)
+            })
+            .collect::<Result<Vec<_>>>()?;
+        ensure!(
+            hand.len() == self.hand_size,
+            "Hand {input:?} should have {} cards",
+            self.hand_size
+        );
+        Ok(Hand(hand))
     }
-}
 
-impl Ord for Hand {
-    fn cmp(&self, other: &Self) -> std::cmp::Ordering {
-        if self.kind() == other.kind() {
-            self.0.cmp(&other.0)
-        } else {
-            self.kind().cmp(&other.kind())
+    fn key(&self, hand: &Hand) -> Result<Self::Key> {
+        let mut strengths = hand
+            .0
+            .iter()
+            .map(|c| self.strength(*c))
+            .collect::<Result<Vec<_>>>()?;
+        if self.tie_break == TieBreak::Sorted {
+            strengths.sort_by_key(|s| Reverse(*s));
         }
+        Ok((self.shape(hand), strengths))
     }
 }
 
-fn cmp_part2(a: &Hand, b: &Hand) -> Ordering {
-    let (kind_a, kind_b) = (a.max_kind(), b.max_kind());
-    if kind_a == kind_b {
-        a.0.into_iter().map(Part2).cmp(b.0.into_iter().map(Part2))
-    } else {
-        kind_a.cmp(&kind_b)
+/// Ways of ranking hands: Camel Cards `Rules`, or `Poker`.
+pub trait Ruleset {
+    type Hand;
+    /// Key by which hands are ranked: stronger hands have bigger keys.
+    type Key: Ord + Hash;
+
+    fn parse_hand(&self, input: &str) -> Result<Self::Hand>;
+
+    /// Ranking key of `hand`. This fails if the hand can't be played under these rules, e.g.
+    /// because it was parsed under other rules with different cards.
+    fn key(&self, hand: &Self::Hand) -> Result<Self::Key>;
+
+    fn parse_bid(&self, input: &str) -> Result<Bid<Self::Hand>> {
+        let (hand_s, bid_s) = input.split_once(' ').wrap_err("Invalid bid")?;
+
+        let hand = self.parse_hand(hand_s)?;
+        let bid = bid_s.parse::<u64>()?;
+
+        Ok(Bid(hand, bid))
     }
 }
 
-pub struct Bid(Hand, u64);
+pub struct Bid<H>(H, u64);
 
-pub fn parse_bid(input: &str) -> Result<Bid> {
-    let (hand_s, bid_s) = input.split_once(' ').wrap_err("Invalid bid")?;
+/// Standard poker rules, for hands of five cards like `AsKdQh2c2h`.
+///
+/// There are no wild cards, and hands of the same kind are ordered by the ranks that make them up
+/// (e.g. the pair, for a pair), then by their kickers.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Poker;
 
-    let hand = parse_hand(hand_s)?;
-    let bid = bid_s.parse::<u64>()?;
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Suit {
+    Clubs,
+    Diamonds,
+    Hearts,
+    Spades,
+}
 
-    Ok(Bid(hand, bid))
+/// Card with a rank from 2 to 14 (ace).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct PokerCard {
+    rank: u8,
+    suit: Suit,
 }
 
-pub fn parse_hand(input: &str) -> Result<Hand> {
-    let hand = input
-        .chars()
-        .map(|c| match c {
-            '2' => Ok(Card::C2),
-            '3' => Ok(Card::C3),
-            '4' => Ok(Card::C4),
-            '5' => Ok(Card::C5),
-            '6' => Ok(Card::C6),
-            '7' => Ok(Card::C7),
-            '8' => Ok(Card::C8),
-            '9' => Ok(Card::C9),
-            'T' => Ok(Card::T),
-            'J' => Ok(Card::J),
-            'Q' => Ok(Card::Q),
-            'K' => Ok(Card::K),
-            'A' => Ok(Card::A),
-            _ => bail!("Invalid card"),
-        })
-        .collect::<Result<Vec<Card>>>()?;
-    let hand = Hand::new(hand[..].try_into()?);
-    Ok(hand)
+impl Display for PokerCard {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let rank = b"23456789TJQKA"[self.rank as usize - 2] as char;
+        let suit = match self.suit {
+            Suit::Clubs => 'c',
+            Suit::Diamonds => 'd',
+            Suit::Hearts => 'h',
+            Suit::Spades => 's',
+        };
+        write!(f, "{rank}{suit}")
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct PokerHand([PokerCard; 5]);
+
+impl Display for PokerHand {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        self.0.iter().try_for_each(|card| write!(f, "{card}"))
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum PokerKind {
+    HighCard,
+    OnePair,
+    TwoPair,
+    ThreeOfAKind,
+    Straight,
+    Flush,
+    FullHouse,
+    FourOfAKind,
+    StraightFlush,
+}
+
+impl From<Kind> for PokerKind {
+    fn from(kind: Kind) -> Self {
+        match kind {
+            Kind::HighCard => Self::HighCard,
+            Kind::OnePair => Self::OnePair,
+            Kind::TwoPair => Self::TwoPair,
+            Kind::ThreeOfAKind => Self::ThreeOfAKind,
+            Kind::FullHouse => Self::FullHouse,
+            Kind::FourOfAKind => Self::FourOfAKind,
+            Kind::FiveOfAKind => unreachable!("there are only four cards of each rank"),
+        }
+    }
+}
+
+impl Poker {
+    pub fn kind(&self, hand: &PokerHand) -> PokerKind {
+        self.rank(hand).0
+    }
+
+    /// Kind of `hand`, then the ranks that break ties, most important first.
+    fn rank(&self, hand: &PokerHand) -> (PokerKind, Vec<u8>) {
+        // Group the ranks by count, so that e.g. for a full house the three of a kind comes first
+        let groups = hand
+            .0
+            .iter()
+            .map(|card| card.rank)
+            .counts()
+            .into_iter()
+            .sorted_by_key(|(rank, cnt)| Reverse((*cnt, *rank)))
+            .collect::<Vec<_>>();
+        let counts = groups.iter().map(|(_, cnt)| *cnt).collect::<Vec<_>>();
+        let ranks = groups.iter().map(|(rank, _)| *rank).collect::<Vec<_>>();
+
+        let flush = hand.0.iter().map(|card| card.suit).all_equal();
+        let straight = (ranks.len() == 5).then(|| Self::straight(&ranks)).flatten();
+        match (straight, flush) {
+            (Some(high), true) => (PokerKind::StraightFlush, vec![high]),
+            (Some(high), false) => (PokerKind::Straight, vec![high]),
+            (None, true) => (PokerKind::Flush, ranks),
+            (None, false) => (Kind::from_counts(&counts).into(), ranks),
+        }
+    }
+
+    /// Highest rank of the straight made by `ranks` (sorted from highest to lowest), if any. The
+    /// ace can also be used as the lowest card, in which case the straight is 5 high.
+    fn straight(ranks: &[u8]) -> Option<u8> {
+        if ranks == [14, 5, 4, 3, 2] {
+            Some(5)
+        } else {
+            ranks
+                .windows(2)
+                .all(|w| w[0] == w[1] + 1)
+                .then_some(ranks[0])
+        }
+    }
+}
+
+impl Ruleset for Poker {
+    type Hand = PokerHand;
+    /// Kind of the hand, then the ranks that break ties, most important first.
+    type Key = (PokerKind, Vec<u8>);
+
+    fn parse_hand(&self, input: &str) -> Result<PokerHand> {
+        let chars = input.chars().collect::<Vec<_>>();
+        ensure!(
+            chars.len() == 10,
+            "Hand {input:?} should have 5 cards, like AsKdQh2c2h"
+        );
+        let cards = chars
+            .chunks(2)
+            .map(|card| {
+                let rank = match card[0] {
+                    '2'..='9' => card[0] as u8 - b'0',
+                    'T' => 10,
+                    'J' => 11,
+                    'Q' => 12,
+                    'K' => 13,
+                    'A' => 14,
+                    c => bail!("Invalid rank {c:?}"),
+                };
+                let suit = match card[1] {
+                    'c' => Suit::Clubs,
+                    'd' => Suit::Diamonds,
+                    'h' => Suit::Hearts,
+                    's' => Suit::Spades,
+                    c => bail!("Invalid suit {c:?}"),
+                };
+                Ok(PokerCard { rank, suit })
+            })
+            .collect::<Result<Vec<_>>>()
+            .wrap_err_with(|| format!("Invalid hand {input:?}"))?;
+        if let Some(card) = cards.iter().duplicates().next() {
+            bail!("Hand {input:?} has {card} several times");
+        }
+        Ok(PokerHand(cards.try_into().expect("5 cards")))
+    }
+
+    fn key(&self, hand: &PokerHand) -> Result<Self::Key> {
+        Ok(self.rank(hand))
+    }
+}
+
+fn generate(size: usize, rng: &mut Rng) -> String {
+    const CARDS: &[u8] = b"23456789TJQKA";
+
+    let mut input = String::new();
+    for _ in 0..size {
+        let hand = (0..5)
+            .map(|_| *rng.choose(CARDS) as char)
+            .collect::<String>();
+        input.push_str(&format!("{hand} {}\n", rng.between(1, 1000)));
+    }
+    input
 }
 
 #[cfg(test)]
 mod tests {
+    use aoc2023::rng::check_generator;
+
     use super::*;
 
+    const EXAMPLE: &str = r"32T3K 765
+T55J5 684
+KK677 28
+KTJJT 220
+QQQJA 483";
+
     #[test]
     fn test_hands() {
-        let hand = parse_hand("32T3K").unwrap();
-        assert_eq!(hand.kind(), Kind::OnePair);
-        let hand = parse_hand("KK677").unwrap();
-        assert_eq!(hand.kind(), Kind::TwoPair);
-        let hand = parse_hand("KTJJT").unwrap();
-        assert_eq!(hand.kind(), Kind::TwoPair);
-        let hand = parse_hand("T55J5").unwrap();
-        assert_eq!(hand.kind(), Kind::ThreeOfAKind);
-        let hand = parse_hand("QQQJA").unwrap();
-        assert_eq!(hand.kind(), Kind::ThreeOfAKind);
+        let rules = Rules::part1();
+        let kind = |hand| Kind::from_counts(&rules.shape(&rules.parse_hand(hand).unwrap()));
+        assert_eq!(kind("32T3K"), Kind::OnePair);
+        assert_eq!(kind("KK677"), Kind::TwoPair);
+        assert_eq!(kind("KTJJT"), Kind::TwoPair);
+        assert_eq!(kind("T55J5"), Kind::ThreeOfAKind);
+        assert_eq!(kind("QQQJA"), Kind::ThreeOfAKind);
+    }
+
+    /// All the hands obtained by replacing the wild cards of `hand` with other cards.
+    fn expand_wild(rules: &Rules, hand: Hand) -> Vec<Hand> {
+        if let Some(idx) = hand.0.iter().position(|c| rules.wild.contains(c)) {
+            rules
+                .order
+                .iter()
+                .filter(|c| !rules.wild.contains(c))
+                .flat_map(|c| {
+                    let mut new_hand = hand.clone();
+                    new_hand.0[idx] = *c;
+                    expand_wild(rules, new_hand)
+                })
+                .collect()
+        } else {
+            vec![hand]
+        }
+    }
+
+    #[test]
+    fn test_joker_kind() {
+        let rules = Rules::part2();
+        let kind = |hand| Kind::from_counts(&rules.shape(&rules.parse_hand(hand).unwrap()));
+        assert_eq!(kind("JJJJJ"), Kind::FiveOfAKind);
+        assert_eq!(kind("KTJJT"), Kind::FourOfAKind);
+        assert_eq!(kind("2233J"), Kind::FullHouse);
+        assert_eq!(kind("2345J"), Kind::OnePair);
+        assert_eq!(kind("32T3K"), Kind::OnePair);
+    }
+
+    #[test]
+    fn test_joker_kind_exhaustive() {
+        let part1 = Rules::part1();
+        let part2 = Rules::part2();
+        for cards in (0..5)
+            .map(|_| part2.order.clone())
+            .multi_cartesian_product()
+        {
+            let hand = Hand(cards);
+            let expected = expand_wild(&part2, hand.clone())
+                .iter()
+                .map(|h| part1.shape(h))
+                .max()
+                .unwrap();
+            assert_eq!(part2.shape(&hand), expected, "{hand}");
+        }
     }
 
     #[test]
     fn test_ordering() {
-        assert!(parse_hand("33332").unwrap() > parse_hand("2AAAA").unwrap());
+        let rules = Rules::part1();
+        let key = |hand| rules.key(&rules.parse_hand(hand).unwrap()).unwrap();
+        assert!(key("33332") > key("2AAAA"));
+        let rules = rules.with_tie_break(TieBreak::Sorted);
+        let key = |hand| rules.key(&rules.parse_hand(hand).unwrap()).unwrap();
+        assert!(key("33332") < key("2AAAA"));
+    }
+
+    #[test]
+    fn test_hand_size() {
+        let rules = Rules::part1().with_hand_size(6).unwrap();
+        let key = |hand| rules.key(&rules.parse_hand(hand).unwrap()).unwrap();
+        // Two three of a kind beat a full house, three pairs beat two pairs, and four of a kind
+        // with a pair beats four of a kind alone
+        assert!(key("222333") > key("AAAKKQ"));
+        assert!(key("223344") > key("AAKKQJ"));
+        assert!(key("222233") > key("AAAAKQ"));
+        assert!(key("222222") > key("AAAAAK"));
+
+        let rules = Rules::part1().with_hand_size(3).unwrap();
+        let input = "222 1\nAAK 10\nAKQ 100";
+        assert_eq!(total_winnings(&mut input.as_bytes(), &rules).unwrap(), 123);
+    }
+
+    #[test]
+    fn test_rules_mismatch() {
+        let hand = Rules::part1().parse_hand("AAKK2").unwrap();
+        let other = Rules::new("23456789").unwrap();
+        assert_eq!(
+            other.key(&hand).unwrap_err().to_string(),
+            "Card 'A' is not part of these rules"
+        );
+    }
+
+    #[test]
+    fn test_rules() {
+        let input = "AAB 10\nBBA 20\nCAB 1\nCCC 5";
+        let rules = Rules::new("ABC").unwrap().with_hand_size(3).unwrap();
+        assert_eq!(total_winnings(&mut input.as_bytes(), &rules).unwrap(), 101);
+        let rules = rules.with_wild("C").unwrap();
+        assert_eq!(total_winnings(&mut input.as_bytes(), &rules).unwrap(), 73);
+
+        assert!(Rules::new("AAB").is_err());
+        assert!(Rules::new("AB").unwrap().with_wild("C").is_err());
+        assert!(Rules::part1().parse_hand("2345").is_err());
+        assert!(Rules::part1().parse_hand("2345X").is_err());
+    }
+
+    #[test]
+    fn test_winnings_tool() {
+        assert_eq!(winnings(EXAMPLE, &[]).unwrap(), "6440");
+        let args = ["wild=J", "order=J23456789TQKA"].map(String::from);
+        assert_eq!(winnings(EXAMPLE, &args).unwrap(), "5905");
+        assert!(winnings(EXAMPLE, &["suits=4".to_string()]).is_err());
+    }
+
+    #[test]
+    fn test_poker_kinds() {
+        let kind = |hand| Poker.kind(&Poker.parse_hand(hand).unwrap());
+        assert_eq!(kind("KhQhJhTh9h"), PokerKind::StraightFlush);
+        assert_eq!(kind("9s9h9d9c2h"), PokerKind::FourOfAKind);
+        assert_eq!(kind("2h2d2cAsAh"), PokerKind::FullHouse);
+        assert_eq!(kind("Ah9h7h5h2h"), PokerKind::Flush);
+        assert_eq!(kind("Th9d8c7s6h"), PokerKind::Straight);
+        assert_eq!(kind("As2d3c4h5s"), PokerKind::Straight);
+        assert_eq!(kind("QsKdAc2h3s"), PokerKind::HighCard);
+        assert_eq!(kind("7h7d7cAs2h"), PokerKind::ThreeOfAKind);
+        assert_eq!(kind("KhKd2c2s9h"), PokerKind::TwoPair);
+        assert_eq!(kind("AhAd9c5s2h"), PokerKind::OnePair);
+        assert_eq!(kind("2h3d4c5s7h"), PokerKind::HighCard);
+    }
+
+    #[test]
+    fn test_poker_ordering() {
+        let key = |hand| Poker.key(&Poker.parse_hand(hand).unwrap()).unwrap();
+        // Ace-low straight is the weakest straight
+        assert!(key("As2d3c4h5s") < key("2d3c4h5s6s"));
+        assert!(key("As2s3s4s5s") > key("KhQhJhTh9d"));
+        // Kickers
+        assert!(key("AhAd9c5s3h") > key("AsAc9d5h2c"));
+        assert!(key("KhKd2c2s9h") < key("KsKc3d3h2c"));
+        // Three of a kind before the pair
+        assert!(key("2h2d2cAsAh") < key("3h3d3c2s2h"));
+        assert!(key("Ah9h7h5h2h") > key("AsKdQcJhTh"));
+        // Suits don't matter
+        assert_eq!(key("AhAd9c5s2h"), key("AsAc9d5h2c"));
+    }
+
+    #[test]
+    fn test_poker_parse() {
+        let hand = Poker.parse_hand("AsKdQh2cTh").unwrap();
+        assert_eq!(hand.to_string(), "AsKdQh2cTh");
+        assert!(Poker.parse_hand("AsKd").is_err());
+        assert!(Poker.parse_hand("AxKdQh2c2h").is_err());
+        assert!(Poker.parse_hand("1sKdQh2c2h").is_err());
+        assert_eq!(
+            Poker.parse_hand("AsAsQh2c2h").unwrap_err().to_string(),
+            "Hand \"AsAsQh2c2h\" has As several times"
+        );
+    }
+
+    #[test]
+    fn test_poker_winnings() {
+        let input = "2h3d4c5s7h 1\nAhAd9c5s2h 10\nAs2d3c4h5s 100\nKhQhJhTh9h 1000";
+        assert_eq!(total_winnings(&mut input.as_bytes(), &Poker).unwrap(), 4321);
+        let input = format!("{input}\nAsAc9d5h2c 5");
+        assert_eq!(total_winnings(&mut input.as_bytes(), &Poker).unwrap(), 5436);
+        let poker_rules = ["rules=poker".to_string()];
+        assert_eq!(winnings(&input, &poker_rules).unwrap(), "5436");
+        assert!(winnings(&input, &[]).is_err());
+        assert!(winnings(EXAMPLE, &poker_rules).is_err());
+        assert!(winnings(&input, &["rules=poker", "wild=J"].map(String::from)).is_err());
+        assert_eq!(
+            poker("", &["KhQhJhTh9h", "2h3d4c5s7h"].map(String::from)).unwrap(),
+            "  1 2h3d4c5s7h HighCard\n  2 KhQhJhTh9h StraightFlush\n"
+        );
     }
 
     #[test]
     fn test_part1() {
+        assert_eq!(part1(EXAMPLE).unwrap(), "6440");
+    }
+
+    #[test]
+    fn test_duplicate_hands() {
         let res = part1(
-            r"32T3K 765
-T55J5 684
-KK677 28
-KTJJT 220
-QQQJA 483",
+            r"32T3K 10
+KK677 5
+32T3K 20",
         )
         .unwrap();
-        assert_eq!(res, "6440");
+        assert_eq!(res, "65");
     }
 
     #[test]
     fn test_part2() {
-        let res = part2(
-            r"32T3K 765
-T55J5 684
-KK677 28
-KTJJT 220
-QQQJA 483",
-        )
-        .unwrap();
-        assert_eq!(res, "5905");
+        assert_eq!(part2(EXAMPLE).unwrap(), "5905");
+    }
+
+    #[test]
+    fn test_generate() {
+        fn bids(input: &str) -> Result<usize> {
+            let rules = Rules::part1();
+            input
+                .lines()
+                .try_for_each(|line| rules.parse_bid(line).map(drop))?;
+            Ok(input.lines().count())
+        }
+
+        check_generator(generate, &[10, 100, 1000], bids);
+        assert_eq!(bids(&generate(50, &mut Rng::new(0))).unwrap(), 50);
+        for seed in 0..10 {
+            let input = generate(50, &mut Rng::new(seed));
+            part1(&input).unwrap();
+            part2(&input).unwrap();
+        }
     }
 }
@@ -1,74 +1,36 @@
 #![allow(dead_code)]
-use std::{
-    collections::{HashMap, HashSet},
-    fmt::{Debug, Display},
-    iter::Cycle,
-    str::Chars,
-};
+use std::{collections::HashSet, iter::Cycle, str::Chars};
 
-use aoc2023::Day;
-use color_eyre::Result;
+use aoc2023::{cycle, label, math, rng::Rng, Day};
+use color_eyre::{
+    eyre::{ContextCompat, WrapErr},
+    Result,
+};
+use itertools::Itertools;
 use once_cell::sync::Lazy;
 use regex::Regex;
 
 inventory::submit! {
-    Day::new(8, part1, part2)
+    Day::new(8, part1, part2).with_generator(generate)
 }
 
 fn part1(input: &str) -> Result<String> {
-    let graph = parse_graph(input);
+    let graph = parse_graph(input)?;
     let count = graph.run_instructions();
 
     Ok(format!("{count}"))
 }
 
 fn part2(input: &str) -> Result<String> {
-    let graph = parse_graph(input);
-    let count = graph.run_instructions_part2();
+    let graph = parse_graph(input)?;
+    let count = graph
+        .run_instructions_part2()
+        .wrap_err("Ghosts never are on end nodes at the same time")?;
 
     Ok(format!("{count}"))
 }
 
-#[derive(Copy, Clone, PartialEq, Eq, Hash)]
-struct Label([u8; 3]);
-
-impl Display for Label {
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        write!(f, "{}", std::str::from_utf8(&self.0).unwrap())
-    }
-}
-
-impl Debug for Label {
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        f.debug_tuple("Label")
-            .field(&std::str::from_utf8(&self.0).unwrap())
-            .finish()
-    }
-}
-
-impl Label {
-    pub fn is_start(&self) -> bool {
-        &self.0 == b"AAA"
-    }
-
-    pub fn is_start2(&self) -> bool {
-        self.0[2] == b'A'
-    }
-
-    pub fn is_end(&self) -> bool {
-        &self.0 == b"ZZZ"
-    }
-
-    pub fn is_end2(&self) -> bool {
-        self.0[2] == b'Z'
-    }
-}
-
-impl From<&str> for Label {
-    fn from(value: &str) -> Self {
-        Self(value.as_bytes().try_into().unwrap())
-    }
-}
+type Label = label::Label<3>;
 
 #[derive(Debug, Clone, PartialEq, Eq)]
 struct Node {
@@ -78,28 +40,33 @@ struct Node {
 
 struct Graph {
     input: String,
-    nodes: HashMap<Label, Node>,
+    /// Nodes indexed by `Label::index`
+    nodes: Vec<Option<Node>>,
 }
 
 impl Graph {
     pub fn new(input: &str) -> Self {
         Self {
             input: input.to_owned(),
-            nodes: HashMap::new(),
+            nodes: vec![None; Label::COUNT],
         }
     }
 
     pub fn insert_node(&mut self, node: Label, left: Label, right: Label) {
-        self.nodes.insert(node, Node { left, right });
+        self.nodes[node.index()] = Some(Node { left, right });
+    }
+
+    fn node(&self, label: Label) -> &Node {
+        self.nodes[label.index()].as_ref().expect("Node not found!")
     }
 
-    pub fn follow_graph(&self, start_node: Label) -> GraphIter {
+    pub fn follow_graph(&self, start_node: Label) -> GraphIter<'_> {
         GraphIter::new(self, start_node)
     }
 
     pub fn run_instructions(&self) -> usize {
-        let start = "AAA".into();
-        let end = "ZZZ".into();
+        let start = "AAA".parse().expect("valid label");
+        let end = "ZZZ".parse().expect("valid label");
         let pos = self
             .follow_graph(start)
             .position(|node| node == end)
@@ -110,42 +77,72 @@ impl Graph {
 
     fn starting_nodes(&self) -> Vec<Label> {
         self.nodes
-            .keys()
-            .filter(|label| label.is_start2())
-            .cloned()
+            .iter()
+            .enumerate()
+            .filter(|(_, node)| node.is_some())
+            .filter_map(|(idx, _)| Label::from_index(idx))
+            .filter(|label| label.ends_with("A"))
             .collect()
     }
 
-    pub fn run_instructions_part2(&self) -> u128 {
-        self.starting_nodes()
+    /// Node reached from `node` by following instruction `inst`.
+    fn step(&self, node: Label, inst: u8) -> Label {
+        let current = self.node(node);
+        match inst {
+            b'L' => current.left,
+            b'R' => current.right,
+            _ => panic!("Invalid input!"),
+        }
+    }
+
+    /// States `(instruction index, node)` visited when starting from `start_node`. Unlike the
+    /// node alone, the state fully determines the rest of the walk, so it is what eventually
+    /// repeats.
+    fn states(&self, start_node: Label) -> impl Iterator<Item = (usize, Label)> + Clone + '_ {
+        let instructions = self.input.as_bytes();
+        cycle::states((0, start_node), move |(idx, node)| {
+            (
+                (idx + 1) % instructions.len(),
+                self.step(*node, instructions[*idx]),
+            )
+        })
+    }
+
+    pub fn run_instructions_part2(&self) -> Option<u128> {
+        // Once in its cycle, a ghost is on an end node at steps `end + k * period` for each of the
+        // end nodes in the cycle, so all the ghosts are on end nodes at the same time at the
+        // solution of a system of congruences. Try every combination of end nodes, and keep the
+        // earliest solution. This ignores end nodes that are only visited before entering the
+        // cycle.
+        let ghosts = self
+            .starting_nodes()
             .into_iter()
-            .map(|node| {
-                let cycle = self.detect_cycle(node).unwrap();
-                (cycle.0) as u128
+            .map(|node| self.detect_cycle(node))
+            .collect::<Vec<_>>();
+        let min_steps = ghosts.iter().map(|(cycle, _)| cycle.tail).max()?.max(1) as i128;
+
+        ghosts
+            .iter()
+            .map(|(cycle, ends)| ends.iter().map(|end| (*end as i128, cycle.period as i128)))
+            .multi_cartesian_product()
+            .filter_map(|congruences| {
+                let (x, l) = math::crt(&congruences)?;
+                // Smallest solution once all the ghosts are in their cycle
+                let k = (min_steps - x).max(0).checked_add(l - 1)? / l;
+                x.checked_add(k.checked_mul(l)?)
             })
-            .reduce(lcm)
-            .unwrap()
+            .min()
+            .map(|steps| steps as u128)
     }
 
-    pub fn detect_cycle(&self, start_node: Label) -> Option<(usize, Label)> {
-        let input_len = self.input.len();
-
-        let mut seen = HashSet::new();
-        for (idx, node) in self
-            .follow_graph(start_node)
-            .enumerate()
-            .filter(|(_idx, node)| node.is_end2())
-        {
-            let idx = idx + 1;
-            let mod_idx = idx % input_len;
-            if seen.contains(&(mod_idx, node)) || mod_idx == 0 {
-                println!("{start_node}: {idx}, {mod_idx}, {node}");
-                return Some((idx, node));
-            } else {
-                seen.insert((mod_idx, node));
-            }
-        }
-        None
+    /// Detect the cycle a ghost starting from `start_node` ends up in, along with the number of
+    /// steps after which it is on an end node within that cycle.
+    pub fn detect_cycle(&self, start_node: Label) -> (cycle::Cycle, Vec<usize>) {
+        let cycle = cycle::brent_iter(self.states(start_node)).expect("walk is infinite");
+        let ends = cycle::matches_in_cycle(self.states(start_node), &cycle, |(_, node)| {
+            node.ends_with("Z")
+        });
+        (cycle, ends)
     }
 }
 
@@ -170,11 +167,7 @@ impl<'g> Iterator for GraphIter<'g> {
 
     fn next(&mut self) -> Option<Self::Item> {
         let inst = self.input_iter.next().expect("can't run out of input");
-        let current = self
-            .graph
-            .nodes
-            .get(&self.current_node)
-            .expect("Node not found!");
+        let current = self.graph.node(self.current_node);
         let next = match inst {
             'L' => current.left,
             'R' => current.right,
@@ -187,32 +180,130 @@ impl<'g> Iterator for GraphIter<'g> {
 
 static RE: Lazy<Regex> = Lazy::new(|| Regex::new(r"(.{3}) = \((.{3}), (.{3})\)").unwrap());
 
-fn parse_graph(input: &str) -> Graph {
-    let lines = input.lines().collect::<Vec<_>>();
-    let instructions = lines[0];
-    lines[2..].iter().map(|line| parse_node(line)).fold(
-        Graph::new(instructions),
-        |mut graph, (node, left, right)| {
-            graph.insert_node(node, left, right);
-            graph
-        },
-    )
+fn parse_graph(input: &str) -> Result<Graph> {
+    let mut lines = input.lines();
+    let instructions = lines.next().wrap_err("Missing instructions")?;
+    let mut graph = Graph::new(instructions);
+    for (idx, line) in lines.enumerate().skip(1) {
+        let (node, left, right) =
+            parse_node(line).wrap_err_with(|| format!("Invalid node on line {}", idx + 2))?;
+        graph.insert_node(node, left, right);
+    }
+    Ok(graph)
 }
 
-fn parse_node(input: &str) -> (Label, Label, Label) {
-    let captures = RE.captures(input).unwrap();
-    let node = captures.get(1).unwrap().as_str().into();
-    let left = captures.get(2).unwrap().as_str().into();
-    let right = captures.get(3).unwrap().as_str().into();
-    (node, left, right)
+fn parse_node(input: &str) -> Result<(Label, Label, Label)> {
+    let captures = RE.captures(input).wrap_err("Expected `AAA = (BBB, CCC)`")?;
+    let node = captures[1].parse()?;
+    let left = captures[2].parse()?;
+    let right = captures[3].parse()?;
+    Ok((node, left, right))
 }
 
-fn lcm(a: u128, b: u128) -> u128 {
-    (a * b) / gcd::euclid_u128(a, b)
+/// Largest network `generate` can make. Labels are made of digits and uppercase letters like in the
+/// examples, and regular nodes can't end with `A` or `Z`, which leaves 34 * 36 * 36 = 44064 labels
+/// for them. Staying well below that keeps picking unused labels at random fast.
+const MAX_SIZE: usize = 30_000;
+
+/// Generates a network of about `size` nodes, which must be at most `MAX_SIZE`.
+///
+/// Each ghost walks a loop whose length is a multiple of the number of instructions: the ghost
+/// starts on a `..A` node, and the `..Z` node at the end of the loop leads back to the node right
+/// after the start. Since the loop length is a multiple of the instructions length, every node of
+/// a loop is always left in the same direction, so the other direction can point anywhere.
+fn generate(size: usize, rng: &mut Rng) -> String {
+    const LABEL_CHARS: &[u8] = b"0123456789ABCDEFGHIJKLMNOPQRSTUVWXYZ";
+    assert!(
+        size <= MAX_SIZE,
+        "Day 8 networks can't have more than {MAX_SIZE} nodes, {size} requested"
+    );
+    let size = size.max(16);
+
+    let mut primes = [2, 3, 5, 7, 11, 13];
+    rng.shuffle(&mut primes);
+    let loop_factors = &primes[..rng.between(1, primes.len() as u64) as usize];
+    let len = (size / loop_factors.iter().sum::<usize>()).max(1);
+    let instructions = (0..len)
+        .map(|_| if rng.chance(0.5) { 'L' } else { 'R' })
+        .collect::<String>();
+
+    let mut used = HashSet::from(["AAA".to_string(), "ZZZ".to_string()]);
+    let mut new_label = |rng: &mut Rng, last: Option<u8>| loop {
+        let mut label = [0; 3];
+        for b in label.iter_mut() {
+            *b = *rng.choose(LABEL_CHARS);
+        }
+        match last {
+            Some(last) => label[2] = last,
+            // Regular nodes must be neither start nor end nodes
+            None if label[2] == b'A' || label[2] == b'Z' => continue,
+            None => {}
+        }
+        let label = String::from_utf8(label.to_vec()).expect("ASCII");
+        if used.insert(label.clone()) {
+            return label;
+        }
+    };
+
+    // (node, next node in the loop, direction taken from the node)
+    let mut loop_edges = vec![];
+    for (ghost, factor) in loop_factors.iter().enumerate() {
+        let (start, end) = if ghost == 0 {
+            ("AAA".to_string(), "ZZZ".to_string())
+        } else {
+            (new_label(rng, Some(b'A')), new_label(rng, Some(b'Z')))
+        };
+        let loop_len = len * factor;
+        let mut nodes = vec![start];
+        nodes.extend((1..loop_len).map(|_| new_label(rng, None)));
+        nodes.push(end.clone());
+        for (step, (node, next)) in nodes.iter().tuple_windows().enumerate() {
+            loop_edges.push((
+                node.clone(),
+                next.clone(),
+                instructions.as_bytes()[step % len],
+            ));
+        }
+        loop_edges.push((end, nodes[1].clone(), instructions.as_bytes()[0]));
+    }
+
+    let mut labels = loop_edges
+        .iter()
+        .map(|(node, _, _)| node.clone())
+        .collect::<Vec<_>>();
+    let decoys = (labels.len()..size)
+        .map(|_| new_label(rng, None))
+        .collect::<Vec<_>>();
+    labels.extend(decoys.iter().cloned());
+
+    let mut lines = loop_edges
+        .into_iter()
+        .map(|(node, next, dir)| {
+            let other = rng.choose(&labels);
+            if dir == b'L' {
+                format!("{node} = ({next}, {other})")
+            } else {
+                format!("{node} = ({other}, {next})")
+            }
+        })
+        .collect::<Vec<_>>();
+    lines.extend(decoys.iter().map(|node| {
+        format!(
+            "{node} = ({}, {})",
+            rng.choose(&labels),
+            rng.choose(&labels)
+        )
+    }));
+    rng.shuffle(&mut lines);
+
+    format!("{instructions}\n\n{}\n", lines.join("\n"))
 }
 
 #[cfg(test)]
 mod tests {
+    use aoc2023::rng::check_generator;
+    use color_eyre::eyre::ensure;
+
     use super::*;
 
     #[test]
@@ -251,4 +342,35 @@ XXX = (XXX, XXX)",
 
         assert_eq!(res, "6");
     }
+
+    #[test]
+    fn test_generate() {
+        fn nodes(input: &str) -> Result<usize> {
+            let graph = parse_graph(input)?;
+            let nodes = graph.nodes.iter().flatten().collect::<Vec<_>>();
+            for node in &nodes {
+                for next in [node.left, node.right] {
+                    ensure!(graph.nodes[next.index()].is_some(), "Unknown node {next}");
+                }
+            }
+            ensure!(
+                graph.nodes[Label::try_from("AAA")?.index()].is_some(),
+                "Missing start node"
+            );
+            Ok(nodes.len())
+        }
+
+        check_generator(generate, &[20, 200, 2000, 20_000, MAX_SIZE], nodes);
+        for seed in 0..10 {
+            let input = generate(200, &mut Rng::new(seed));
+            part1(&input).unwrap();
+            part2(&input).unwrap();
+        }
+    }
+
+    #[test]
+    #[should_panic(expected = "can't have more than")]
+    fn test_generate_too_large() {
+        generate(MAX_SIZE + 1, &mut Rng::new(0));
+    }
 }
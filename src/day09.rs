@@ -1,28 +1,32 @@
-use aoc2023::Day;
+use std::io::BufRead;
+
+use aoc2023::{parse, rng::Rng, Day};
 use color_eyre::Result;
-use nom::{bytes::complete::tag, character::complete::i64, multi::separated_list1, IResult};
+use nom::{character::complete::i64, IResult};
 
 inventory::submit! {
-    Day::new(9, part1, part2)
+    Day::new(9, part1, part2).with_streaming(part1_stream, part2_stream).with_generator(generate)
 }
 
 fn part1(input: &str) -> Result<String> {
-    let sequences = input
-        .lines()
-        .map(|line| parse_history(line).expect("invalid input").1)
-        .collect::<Vec<_>>();
-
-    let total: i64 = sequences.into_iter().map(|seq| extrapolate(&seq)).sum();
-    Ok(format!("{total}"))
+    part1_stream(&mut input.as_bytes())
 }
 
 fn part2(input: &str) -> Result<String> {
-    let sequences = input
-        .lines()
-        .map(|line| parse_history(line).expect("invalid input").1)
-        .collect::<Vec<_>>();
+    part2_stream(&mut input.as_bytes())
+}
 
-    let total: i64 = sequences.into_iter().map(|seq| extrapolate2(&seq)).sum();
+fn part1_stream(input: &mut dyn BufRead) -> Result<String> {
+    let total = parse::read_lines(input, parse_history)
+        .map(|seq| Ok(extrapolate(&seq?)))
+        .sum::<Result<i64>>()?;
+    Ok(format!("{total}"))
+}
+
+fn part2_stream(input: &mut dyn BufRead) -> Result<String> {
+    let total = parse::read_lines(input, parse_history)
+        .map(|seq| Ok(extrapolate2(&seq?)))
+        .sum::<Result<i64>>()?;
     Ok(format!("{total}"))
 }
 
@@ -56,11 +60,34 @@ fn extrapolate2(seq: &[i64]) -> i64 {
     extrapolate_inner(seq, 0, 1)
 }
 fn parse_history(input: &str) -> IResult<&str, Vec<i64>> {
-    separated_list1(tag(" "), i64)(input)
+    parse::numbers(i64)(input)
+}
+
+/// Generates `size` sequences of values of random polynomials.
+fn generate(size: usize, rng: &mut Rng) -> String {
+    const LEN: i64 = 21;
+
+    let mut input = String::new();
+    for _ in 0..size {
+        let coefs = (0..=rng.between(0, 5))
+            .map(|_| rng.between_i64(-10, 10))
+            .collect::<Vec<_>>();
+        let values = (0..LEN)
+            .map(|x| coefs.iter().rev().fold(0, |acc, c| acc * x + c))
+            .map(|v| v.to_string())
+            .collect::<Vec<_>>()
+            .join(" ");
+        input.push_str(&values);
+        input.push('\n');
+    }
+    input
 }
 
 #[cfg(test)]
 mod tests {
+    use aoc2023::rng::check_generator;
+    use color_eyre::eyre::ensure;
+
     use super::*;
 
     #[test]
@@ -74,4 +101,27 @@ mod tests {
         assert_eq!(extrapolate2(&[1, 3, 6, 10, 15, 21]), 0);
         assert_eq!(extrapolate2(&[10, 13, 16, 21, 30, 45]), 5);
     }
+
+    #[test]
+    fn test_generate() {
+        fn histories(input: &str) -> Result<usize> {
+            let histories = parse::read_lines(&mut input.as_bytes(), parse_history)
+                .collect::<Result<Vec<_>>>()?;
+            for history in &histories {
+                ensure!(
+                    history.len() == 21,
+                    "History {history:?} has the wrong length"
+                );
+            }
+            Ok(histories.len())
+        }
+
+        check_generator(generate, &[10, 100, 1000], histories);
+        assert_eq!(histories(&generate(20, &mut Rng::new(0))).unwrap(), 20);
+        for seed in 0..10 {
+            let input = generate(20, &mut Rng::new(seed));
+            part1(&input).unwrap();
+            part2(&input).unwrap();
+        }
+    }
 }
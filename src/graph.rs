@@ -0,0 +1,263 @@
+//! Directed graphs with labeled nodes and edges.
+//!
+//! Nodes live in a slotmap arena and are referred to by `NodeId`, which stays valid when other
+//! nodes are removed. Node labels are unique, so a node can also be looked up by its label. The
+//! search algorithms are thin wrappers around `pathfinding`.
+
+use std::{
+    collections::{HashMap, HashSet},
+    fmt::{Display, Write},
+    hash::Hash,
+};
+
+use pathfinding::{num_traits::Zero, prelude as pf};
+use slotmap::{new_key_type, SlotMap};
+
+new_key_type! {
+    pub struct NodeId;
+}
+
+#[derive(Debug, Clone)]
+struct NodeData<N, E> {
+    label: N,
+    edges: Vec<(NodeId, E)>,
+}
+
+#[derive(Debug, Clone)]
+pub struct DiGraph<N, E> {
+    nodes: SlotMap<NodeId, NodeData<N, E>>,
+    ids: HashMap<N, NodeId>,
+}
+
+impl<N, E> Default for DiGraph<N, E> {
+    fn default() -> Self {
+        Self {
+            nodes: SlotMap::with_key(),
+            ids: HashMap::new(),
+        }
+    }
+}
+
+impl<N, E> DiGraph<N, E>
+where
+    N: Eq + Hash + Clone,
+{
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn len(&self) -> usize {
+        self.nodes.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.nodes.is_empty()
+    }
+
+    /// Id of the node labeled `label`, which is added if it doesn't exist yet.
+    pub fn add_node(&mut self, label: N) -> NodeId {
+        if let Some(id) = self.ids.get(&label) {
+            return *id;
+        }
+        let id = self.nodes.insert(NodeData {
+            label: label.clone(),
+            edges: vec![],
+        });
+        self.ids.insert(label, id);
+        id
+    }
+
+    /// Add an edge from `from` to `to`. Parallel edges are allowed.
+    pub fn add_edge(&mut self, from: NodeId, to: NodeId, edge: E) {
+        assert!(self.nodes.contains_key(to), "Unknown node {to:?}");
+        self.nodes[from].edges.push((to, edge));
+    }
+
+    /// Remove a node along with all the edges to and from it, returning its label.
+    pub fn remove_node(&mut self, id: NodeId) -> Option<N> {
+        let data = self.nodes.remove(id)?;
+        self.ids.remove(&data.label);
+        for (_, node) in self.nodes.iter_mut() {
+            node.edges.retain(|(to, _)| *to != id);
+        }
+        Some(data.label)
+    }
+
+    pub fn node_id(&self, label: &N) -> Option<NodeId> {
+        self.ids.get(label).copied()
+    }
+
+    pub fn label(&self, id: NodeId) -> &N {
+        &self.nodes[id].label
+    }
+
+    pub fn nodes(&self) -> impl Iterator<Item = NodeId> + '_ {
+        self.nodes.keys()
+    }
+
+    /// Outgoing edges of `id`, with their destination.
+    pub fn edges(&self, id: NodeId) -> impl Iterator<Item = (NodeId, &E)> {
+        self.nodes[id].edges.iter().map(|(to, e)| (*to, e))
+    }
+
+    pub fn successors(&self, id: NodeId) -> impl Iterator<Item = NodeId> + '_ {
+        self.nodes[id].edges.iter().map(|(to, _)| *to)
+    }
+
+    /// Shortest path (in number of edges) from `start` to a node matching `goal`.
+    pub fn bfs(&self, start: NodeId, goal: impl FnMut(&NodeId) -> bool) -> Option<Vec<NodeId>> {
+        pf::bfs(&start, |id| self.successors(*id), goal)
+    }
+
+    /// Some path from `start` to a node matching `goal`, found depth-first.
+    pub fn dfs(&self, start: NodeId, goal: impl FnMut(&NodeId) -> bool) -> Option<Vec<NodeId>> {
+        pf::dfs(start, |id| self.successors(*id).collect::<Vec<_>>(), goal)
+    }
+
+    /// Cheapest path from `start` to a node matching `goal`, along with its cost, where the cost
+    /// of each edge is given by `cost`.
+    pub fn dijkstra<C>(
+        &self,
+        start: NodeId,
+        cost: impl Fn(&E) -> C,
+        goal: impl FnMut(&NodeId) -> bool,
+    ) -> Option<(Vec<NodeId>, C)>
+    where
+        C: Zero + Ord + Copy,
+    {
+        pf::dijkstra(
+            &start,
+            |id| self.edges(*id).map(|(to, e)| (to, cost(e))),
+            goal,
+        )
+    }
+
+    /// All the nodes in topological order, or a node that is part of a cycle.
+    pub fn topological_sort(&self) -> Result<Vec<NodeId>, NodeId> {
+        let nodes = self.nodes().collect::<Vec<_>>();
+        pf::topological_sort(&nodes, |id| self.successors(*id))
+    }
+
+    /// Strongly connected components, each one given as a list of nodes.
+    pub fn strongly_connected_components(&self) -> Vec<Vec<NodeId>> {
+        let nodes = self.nodes().collect::<Vec<_>>();
+        pf::strongly_connected_components(&nodes, |id| self.successors(*id))
+    }
+
+    /// Nodes reachable from `start`, including `start` itself.
+    pub fn reachable(&self, start: NodeId) -> HashSet<NodeId> {
+        pf::bfs_reach(start, |id| self.successors(*id)).collect()
+    }
+
+    pub fn is_reachable(&self, from: NodeId, to: NodeId) -> bool {
+        pf::bfs_reach(from, |id| self.successors(*id)).any(|id| id == to)
+    }
+}
+
+impl<N, E> DiGraph<N, E>
+where
+    N: Eq + Hash + Clone + Display,
+    E: Display,
+{
+    /// Render the graph in Graphviz DOT format.
+    pub fn to_dot(&self) -> String {
+        let names = self
+            .nodes()
+            .enumerate()
+            .map(|(i, id)| (id, format!("n{i}")))
+            .collect::<HashMap<_, _>>();
+        let mut dot = String::from("digraph {\n");
+        for (id, node) in self.nodes.iter() {
+            let label = escape(&node.label.to_string());
+            writeln!(dot, "    {} [label=\"{label}\"];", names[&id]).expect("infallible");
+        }
+        for (id, node) in self.nodes.iter() {
+            for (to, edge) in &node.edges {
+                let label = escape(&edge.to_string());
+                writeln!(
+                    dot,
+                    "    {} -> {} [label=\"{label}\"];",
+                    names[&id], names[to]
+                )
+                .expect("infallible");
+            }
+        }
+        dot.push_str("}\n");
+        dot
+    }
+}
+
+fn escape(s: &str) -> String {
+    s.replace('\\', "\\\\").replace('"', "\\\"")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// a -> b -> c -> d, with a shortcut a -> c and a loop c -> b
+    fn graph() -> (DiGraph<&'static str, u32>, Vec<NodeId>) {
+        let mut g = DiGraph::new();
+        let ids = ["a", "b", "c", "d"].map(|l| g.add_node(l)).to_vec();
+        g.add_edge(ids[0], ids[1], 1);
+        g.add_edge(ids[1], ids[2], 1);
+        g.add_edge(ids[2], ids[3], 1);
+        g.add_edge(ids[0], ids[2], 5);
+        g.add_edge(ids[2], ids[1], 1);
+        (g, ids)
+    }
+
+    #[test]
+    fn test_nodes() {
+        let (mut g, ids) = graph();
+        assert_eq!(g.len(), 4);
+        assert_eq!(g.add_node("a"), ids[0]);
+        assert_eq!(g.node_id(&"c"), Some(ids[2]));
+        assert_eq!(g.remove_node(ids[2]), Some("c"));
+        assert_eq!(g.node_id(&"c"), None);
+        assert_eq!(g.successors(ids[0]).collect::<Vec<_>>(), vec![ids[1]]);
+        assert_eq!(*g.label(ids[3]), "d");
+    }
+
+    #[test]
+    fn test_search() {
+        let (g, ids) = graph();
+        let d = ids[3];
+        assert_eq!(g.bfs(ids[0], |id| *id == d), Some(vec![ids[0], ids[2], d]));
+        assert_eq!(g.dfs(ids[0], |id| *id == d).unwrap().last(), Some(&d));
+        assert_eq!(
+            g.dijkstra(ids[0], |c| *c, |id| *id == d),
+            Some((vec![ids[0], ids[1], ids[2], d], 3))
+        );
+        assert_eq!(g.bfs(d, |id| *id == ids[0]), None);
+        assert!(g.is_reachable(ids[1], d));
+        assert!(!g.is_reachable(ids[1], ids[0]));
+        assert_eq!(g.reachable(ids[2]).len(), 3);
+    }
+
+    #[test]
+    fn test_components() {
+        let (mut g, ids) = graph();
+        let mut sccs = g.strongly_connected_components();
+        sccs.iter_mut().for_each(|c| c.sort());
+        sccs.sort_by_key(|c| c.len());
+        assert_eq!(sccs.len(), 3);
+        assert_eq!(sccs[2], vec![ids[1], ids[2]]);
+
+        assert!(g.topological_sort().is_err());
+        g.remove_node(ids[1]);
+        assert_eq!(g.topological_sort(), Ok(vec![ids[0], ids[2], ids[3]]));
+    }
+
+    #[test]
+    fn test_dot() {
+        let mut g = DiGraph::new();
+        let a = g.add_node("a");
+        let b = g.add_node("\"b\"");
+        g.add_edge(a, b, 'L');
+        assert_eq!(
+            g.to_dot(),
+            "digraph {\n    n0 [label=\"a\"];\n    n1 [label=\"\\\"b\\\"\"];\n    n0 -> n1 [label=\"L\"];\n}\n"
+        );
+    }
+}
@@ -0,0 +1,346 @@
+use std::{
+    fmt::Display,
+    ops::{Index, IndexMut},
+};
+
+use color_eyre::{
+    eyre::{ensure, eyre},
+    Result,
+};
+
+use crate::point::{Direction, UPoint, Vec2};
+
+/// A rectangular grid of cells, stored row by row.
+///
+/// Positions are `UPoint`s, with `x` the column and `y` the row, `(0, 0)` being the top-left
+/// corner.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Grid<T> {
+    width: usize,
+    height: usize,
+    cells: Vec<T>,
+}
+
+impl<T> Grid<T> {
+    /// Build a grid from its cells, given row by row.
+    pub fn new(width: usize, height: usize, cells: Vec<T>) -> Result<Self> {
+        ensure!(
+            cells.len() == width * height,
+            "Expected {} cells for a {width}x{height} grid, got {}",
+            width * height,
+            cells.len()
+        );
+        Ok(Self {
+            width,
+            height,
+            cells,
+        })
+    }
+
+    /// Parse a grid from text, one row per line, converting each character with `f`.
+    pub fn parse_with(input: &str, mut f: impl FnMut(char) -> Result<T>) -> Result<Self> {
+        let mut width = None;
+        let mut cells = vec![];
+        let mut height = 0;
+        for (y, line) in input.lines().enumerate() {
+            let len = cells.len();
+            for c in line.chars() {
+                cells.push(f(c)?);
+            }
+            let w = cells.len() - len;
+            let width = *width.get_or_insert(w);
+            ensure!(w == width, "Line {} has {w} cells, expected {width}", y + 1);
+            height += 1;
+        }
+        Self::new(width.unwrap_or(0), height, cells)
+    }
+
+    pub fn width(&self) -> usize {
+        self.width
+    }
+
+    pub fn height(&self) -> usize {
+        self.height
+    }
+
+    pub fn in_bounds(&self, p: UPoint) -> bool {
+        p.x < self.width && p.y < self.height
+    }
+
+    pub fn get(&self, p: UPoint) -> Option<&T> {
+        self.in_bounds(p)
+            .then(|| &self.cells[p.y * self.width + p.x])
+    }
+
+    pub fn get_mut(&mut self, p: UPoint) -> Option<&mut T> {
+        if self.in_bounds(p) {
+            Some(&mut self.cells[p.y * self.width + p.x])
+        } else {
+            None
+        }
+    }
+
+    /// All positions of the grid, row by row.
+    pub fn positions(&self) -> impl Iterator<Item = UPoint> {
+        let width = self.width;
+        (0..self.height).flat_map(move |y| (0..width).map(move |x| UPoint::new(x, y)))
+    }
+
+    /// All cells of the grid with their position, row by row.
+    pub fn iter(&self) -> impl Iterator<Item = (UPoint, &T)> {
+        self.positions().zip(self.cells.iter())
+    }
+
+    /// Position `p + v`, if it is inside the grid.
+    pub fn offset(&self, p: UPoint, v: Vec2) -> Option<UPoint> {
+        p.checked_add(v).filter(|p| self.in_bounds(*p))
+    }
+
+    /// Neighbour of `p` in direction `dir`, if it is inside the grid.
+    pub fn step(&self, p: UPoint, dir: Direction) -> Option<UPoint> {
+        p.step_within(dir, self.width, self.height)
+    }
+
+    /// Orthogonal neighbours of `p` that are inside the grid.
+    pub fn neighbours4(&self, p: UPoint) -> impl Iterator<Item = UPoint> + '_ {
+        Direction::CARDINAL
+            .into_iter()
+            .filter_map(move |dir| self.step(p, dir))
+    }
+
+    /// Orthogonal and diagonal neighbours of `p` that are inside the grid.
+    pub fn neighbours8(&self, p: UPoint) -> impl Iterator<Item = UPoint> + '_ {
+        Direction::ALL
+            .into_iter()
+            .filter_map(move |dir| self.step(p, dir))
+    }
+
+    pub fn row(&self, y: usize) -> &[T] {
+        &self.cells[y * self.width..(y + 1) * self.width]
+    }
+
+    pub fn rows(&self) -> impl Iterator<Item = &[T]> {
+        // `chunks` panics on a zero chunk size, which happens for an empty grid
+        self.cells.chunks(self.width.max(1))
+    }
+
+    /// Cells of column `x`, from top to bottom.
+    ///
+    /// Panics if `x` is not below the width of the grid, like `row` does for rows.
+    pub fn column(&self, x: usize) -> impl Iterator<Item = &T> {
+        assert!(x < self.width, "Column {x} is out of the grid");
+        self.cells.iter().skip(x).step_by(self.width)
+    }
+
+    /// Cells along the diagonal going down and to the right from `p`.
+    pub fn diagonal(&self, p: UPoint) -> impl Iterator<Item = &T> {
+        self.ray(p, Direction::SE)
+    }
+
+    /// Cells along the anti-diagonal going down and to the left from `p`.
+    pub fn anti_diagonal(&self, p: UPoint) -> impl Iterator<Item = &T> {
+        self.ray(p, Direction::SW)
+    }
+
+    /// Cells from `p` (included) in direction `dir`, up to the edge of the grid.
+    pub fn ray(&self, p: UPoint, dir: Direction) -> impl Iterator<Item = &T> {
+        std::iter::successors(self.in_bounds(p).then_some(p), move |p| self.step(*p, dir))
+            .map(|p| &self[p])
+    }
+
+    /// Positions of all the cells matching `pred`, row by row.
+    pub fn find_all<'a>(
+        &'a self,
+        mut pred: impl FnMut(&T) -> bool + 'a,
+    ) -> impl Iterator<Item = UPoint> + 'a {
+        self.iter().filter_map(move |(p, c)| pred(c).then_some(p))
+    }
+
+    pub fn map<U>(&self, f: impl FnMut(&T) -> U) -> Grid<U> {
+        Grid {
+            width: self.width,
+            height: self.height,
+            cells: self.cells.iter().map(f).collect(),
+        }
+    }
+}
+
+impl<T: Clone> Grid<T> {
+    /// Grid of the given size with every cell set to `value`.
+    pub fn filled(width: usize, height: usize, value: T) -> Self {
+        Self {
+            width,
+            height,
+            cells: vec![value; width * height],
+        }
+    }
+
+    pub fn transpose(&self) -> Self {
+        Self {
+            width: self.height,
+            height: self.width,
+            cells: (0..self.width)
+                .flat_map(|x| self.column(x).cloned())
+                .collect(),
+        }
+    }
+
+    /// Rotate the grid by 90° clockwise.
+    pub fn rotate_cw(&self) -> Self {
+        let mut grid = self.transpose();
+        for row in grid.cells.chunks_mut(grid.width.max(1)) {
+            row.reverse();
+        }
+        grid
+    }
+
+    /// Rotate the grid by 90° counter-clockwise.
+    pub fn rotate_ccw(&self) -> Self {
+        let mut grid = self.transpose();
+        let rows = grid
+            .cells
+            .chunks(grid.width.max(1))
+            .rev()
+            .flatten()
+            .cloned()
+            .collect();
+        grid.cells = rows;
+        grid
+    }
+}
+
+impl<T> Grid<T>
+where
+    T: TryFrom<char>,
+    T::Error: Display,
+{
+    /// Parse a grid from text, one row per line, converting each character to a cell.
+    pub fn parse(input: &str) -> Result<Self> {
+        Self::parse_with(input, |c| {
+            T::try_from(c).map_err(|e| eyre!("Invalid cell {c:?}: {e}"))
+        })
+    }
+}
+
+impl<T> Index<UPoint> for Grid<T> {
+    type Output = T;
+
+    fn index(&self, p: UPoint) -> &Self::Output {
+        assert!(self.in_bounds(p), "{p} is out of bounds");
+        &self.cells[p.y * self.width + p.x]
+    }
+}
+
+impl<T> IndexMut<UPoint> for Grid<T> {
+    fn index_mut(&mut self, p: UPoint) -> &mut Self::Output {
+        assert!(self.in_bounds(p), "{p} is out of bounds");
+        &mut self.cells[p.y * self.width + p.x]
+    }
+}
+
+/// Cells that can be rendered as a single character.
+pub trait Cell {
+    fn to_char(&self) -> char;
+}
+
+impl Cell for u8 {
+    fn to_char(&self) -> char {
+        *self as char
+    }
+}
+
+impl Cell for char {
+    fn to_char(&self) -> char {
+        *self
+    }
+}
+
+impl Cell for bool {
+    fn to_char(&self) -> char {
+        if *self {
+            '#'
+        } else {
+            '.'
+        }
+    }
+}
+
+impl<T: Cell> Display for Grid<T> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        for row in self.rows() {
+            for c in row {
+                write!(f, "{}", c.to_char())?;
+            }
+            writeln!(f)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const INPUT: &str = "abc\ndef";
+
+    #[test]
+    fn test_parse() {
+        let grid = Grid::<u8>::parse(INPUT).unwrap();
+        assert_eq!((grid.width(), grid.height()), (3, 2));
+        assert_eq!(grid[UPoint::new(1, 1)], b'e');
+        assert_eq!(grid.get(UPoint::new(3, 0)), None);
+        assert_eq!(grid.to_string(), "abc\ndef\n");
+        assert!(Grid::<u8>::parse("abc\nde").is_err());
+        assert!(Grid::<u8>::parse("ab\nd€").is_err());
+    }
+
+    #[test]
+    fn test_neighbours() {
+        let grid = Grid::<u8>::parse(INPUT).unwrap();
+        assert_eq!(
+            grid.neighbours4(UPoint::new(0, 0)).collect::<Vec<_>>(),
+            vec![UPoint::new(1, 0), UPoint::new(0, 1)]
+        );
+        assert_eq!(grid.neighbours8(UPoint::new(2, 1)).count(), 3);
+        assert_eq!(grid.neighbours8(UPoint::new(1, 0)).count(), 5);
+    }
+
+    #[test]
+    fn test_views() {
+        let grid = Grid::<u8>::parse(INPUT).unwrap();
+        assert_eq!(grid.row(1), b"def");
+        assert_eq!(grid.column(2).copied().collect::<Vec<_>>(), b"cf");
+        assert_eq!(
+            grid.diagonal(UPoint::new(0, 0))
+                .copied()
+                .collect::<Vec<_>>(),
+            b"ae"
+        );
+        assert_eq!(
+            grid.anti_diagonal(UPoint::new(2, 0))
+                .copied()
+                .collect::<Vec<_>>(),
+            b"ce"
+        );
+        assert_eq!(
+            grid.find_all(|c| b"aeiou".contains(c)).collect::<Vec<_>>(),
+            vec![UPoint::new(0, 0), UPoint::new(1, 1)]
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "Column 3 is out of the grid")]
+    fn test_column_out_of_range() {
+        // Would otherwise be the first cell of the second row
+        Grid::<u8>::parse(INPUT).unwrap().column(3).count();
+    }
+
+    #[test]
+    fn test_transform() {
+        let grid = Grid::<u8>::parse(INPUT).unwrap();
+        assert_eq!(grid.transpose().to_string(), "ad\nbe\ncf\n");
+        assert_eq!(grid.rotate_cw().to_string(), "da\neb\nfc\n");
+        assert_eq!(grid.rotate_ccw().to_string(), "cf\nbe\nad\n");
+        assert_eq!(grid.rotate_cw().rotate_ccw(), grid);
+    }
+}
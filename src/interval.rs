@@ -0,0 +1,289 @@
+use std::{fmt::Display, ops::Sub};
+
+/// Half-open interval `[start, end)`. Intervals with `start >= end` are empty.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct Interval<T> {
+    pub start: T,
+    pub end: T,
+}
+
+impl<T: Copy + Ord> Interval<T> {
+    pub fn new(start: T, end: T) -> Self {
+        Self { start, end }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.start >= self.end
+    }
+
+    pub fn contains(&self, v: T) -> bool {
+        self.start <= v && v < self.end
+    }
+
+    pub fn overlaps(&self, other: &Self) -> bool {
+        !self.intersection(other).is_empty()
+    }
+
+    /// Whether `self` and `other` overlap or touch, i.e. whether their union is an interval.
+    pub fn is_connected(&self, other: &Self) -> bool {
+        self.start <= other.end && other.start <= self.end
+    }
+
+    pub fn intersection(&self, other: &Self) -> Self {
+        Self::new(self.start.max(other.start), self.end.min(other.end))
+    }
+
+    /// Parts of `self` before and after `other`, either of which may be empty.
+    pub fn difference(&self, other: &Self) -> (Self, Self) {
+        (
+            Self::new(self.start, self.end.min(other.start)),
+            Self::new(self.start.max(other.end), self.end),
+        )
+    }
+
+    /// Split `self` into the parts below and from `v`, either of which may be empty.
+    pub fn split_at(&self, v: T) -> (Self, Self) {
+        let v = v.clamp(self.start, self.end.max(self.start));
+        (Self::new(self.start, v), Self::new(v, self.end))
+    }
+
+    /// Split `self` across `pieces`, which must be sorted and non-overlapping (like the domain of
+    /// a piecewise function).
+    ///
+    /// Returns the non-empty parts of `self` in order, each along with the index of the piece it
+    /// falls into, or `None` for the gaps between pieces.
+    pub fn split_across(&self, pieces: &[Self]) -> Vec<(Self, Option<usize>)> {
+        let mut parts = vec![];
+        if self.is_empty() {
+            return parts;
+        }
+
+        // Skip the pieces that end before `self` starts
+        let first = pieces.partition_point(|p| p.end <= self.start);
+        let mut rest = *self;
+        for (idx, piece) in pieces.iter().enumerate().skip(first) {
+            if rest.is_empty() || piece.start >= rest.end {
+                break;
+            }
+            let (gap, after) = rest.split_at(piece.start);
+            if !gap.is_empty() {
+                parts.push((gap, None));
+            }
+            let (covered, after) = after.split_at(piece.end);
+            if !covered.is_empty() {
+                parts.push((covered, Some(idx)));
+            }
+            rest = after;
+        }
+        if !rest.is_empty() {
+            parts.push((rest, None));
+        }
+        parts
+    }
+}
+
+impl<T: Copy + Ord + Sub<Output = T> + Default> Interval<T> {
+    /// Number of values in the interval.
+    pub fn len(&self) -> T {
+        if self.is_empty() {
+            T::default()
+        } else {
+            self.end - self.start
+        }
+    }
+}
+
+impl<T: Display> Display for Interval<T> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "[{}, {})", self.start, self.end)
+    }
+}
+
+/// A set of values stored as intervals.
+///
+/// The intervals are kept normalized: sorted, non-empty, and neither overlapping nor touching, so
+/// that two sets containing the same values are equal.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct IntervalSet<T>(Vec<Interval<T>>);
+
+impl<T> Default for IntervalSet<T> {
+    fn default() -> Self {
+        Self(vec![])
+    }
+}
+
+impl<T: Copy + Ord> IntervalSet<T> {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn intervals(&self) -> &[Interval<T>] {
+        &self.0
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    /// The interval containing `v`, if any.
+    pub fn find(&self, v: T) -> Option<&Interval<T>> {
+        let idx = self.0.partition_point(|iv| iv.end <= v);
+        self.0.get(idx).filter(|iv| iv.contains(v))
+    }
+
+    pub fn contains(&self, v: T) -> bool {
+        self.find(v).is_some()
+    }
+
+    pub fn insert(&mut self, iv: Interval<T>) {
+        if iv.is_empty() {
+            return;
+        }
+        // All the intervals connected to `iv` are contiguous, so merge them into one
+        let from = self.0.partition_point(|other| other.end < iv.start);
+        let to = self.0.partition_point(|other| other.start <= iv.end);
+        let merged = self.0[from..to].iter().fold(iv, |acc, other| {
+            Interval::new(acc.start.min(other.start), acc.end.max(other.end))
+        });
+        self.0.splice(from..to, [merged]);
+    }
+
+    pub fn union(&self, other: &Self) -> Self {
+        let mut set = self.clone();
+        for iv in &other.0 {
+            set.insert(*iv);
+        }
+        set
+    }
+
+    pub fn intersection(&self, other: &Self) -> Self {
+        let mut intervals = vec![];
+        let (mut i, mut j) = (0, 0);
+        while i < self.0.len() && j < other.0.len() {
+            let (a, b) = (self.0[i], other.0[j]);
+            let common = a.intersection(&b);
+            if !common.is_empty() {
+                intervals.push(common);
+            }
+            if a.end < b.end {
+                i += 1;
+            } else {
+                j += 1;
+            }
+        }
+        Self(intervals)
+    }
+
+    pub fn difference(&self, other: &Self) -> Self {
+        let mut intervals = vec![];
+        for iv in &self.0 {
+            let mut rest = *iv;
+            let first = other.0.partition_point(|o| o.end <= iv.start);
+            for o in other.0[first..].iter().take_while(|o| o.start < iv.end) {
+                let (before, after) = rest.difference(o);
+                if !before.is_empty() {
+                    intervals.push(before);
+                }
+                rest = after;
+            }
+            if !rest.is_empty() {
+                intervals.push(rest);
+            }
+        }
+        Self(intervals)
+    }
+}
+
+impl<T: Copy + Ord> FromIterator<Interval<T>> for IntervalSet<T> {
+    fn from_iter<I: IntoIterator<Item = Interval<T>>>(iter: I) -> Self {
+        let mut intervals = iter
+            .into_iter()
+            .filter(|iv| !iv.is_empty())
+            .collect::<Vec<_>>();
+        intervals.sort();
+        let mut normalized: Vec<Interval<T>> = Vec::with_capacity(intervals.len());
+        for iv in intervals {
+            match normalized.last_mut() {
+                Some(last) if last.is_connected(&iv) => last.end = last.end.max(iv.end),
+                _ => normalized.push(iv),
+            }
+        }
+        Self(normalized)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn set(intervals: &[(i64, i64)]) -> IntervalSet<i64> {
+        intervals
+            .iter()
+            .map(|(start, end)| Interval::new(*start, *end))
+            .collect()
+    }
+
+    #[test]
+    fn test_interval() {
+        let iv = Interval::new(2, 8);
+        assert_eq!(iv.len(), 6);
+        assert!(iv.contains(2) && !iv.contains(8));
+        assert_eq!(iv.intersection(&Interval::new(5, 10)), Interval::new(5, 8));
+        assert!(!iv.overlaps(&Interval::new(8, 10)));
+        assert!(iv.is_connected(&Interval::new(8, 10)));
+        assert_eq!(
+            iv.difference(&Interval::new(4, 5)),
+            (Interval::new(2, 4), Interval::new(5, 8))
+        );
+        assert_eq!(Interval::new(5, 2).len(), 0);
+    }
+
+    #[test]
+    fn test_split_across() {
+        let pieces = [
+            Interval::new(0, 3),
+            Interval::new(5, 7),
+            Interval::new(7, 9),
+        ];
+        assert_eq!(
+            Interval::new(2, 12).split_across(&pieces),
+            vec![
+                (Interval::new(2, 3), Some(0)),
+                (Interval::new(3, 5), None),
+                (Interval::new(5, 7), Some(1)),
+                (Interval::new(7, 9), Some(2)),
+                (Interval::new(9, 12), None),
+            ]
+        );
+        assert_eq!(
+            Interval::new(3, 4).split_across(&pieces),
+            vec![(Interval::new(3, 4), None)]
+        );
+        assert_eq!(
+            Interval::new(5, 6).split_across(&pieces),
+            vec![(Interval::new(5, 6), Some(1))]
+        );
+    }
+
+    #[test]
+    fn test_normalize() {
+        let s = set(&[(5, 7), (0, 2), (1, 3), (3, 4), (9, 9)]);
+        assert_eq!(s.intervals(), set(&[(0, 4), (5, 7)]).intervals());
+        assert!(s.contains(3) && !s.contains(4) && s.contains(6));
+        assert_eq!(s.find(5), Some(&Interval::new(5, 7)));
+
+        let mut s = s;
+        s.insert(Interval::new(4, 5));
+        assert_eq!(s, set(&[(0, 7)]));
+    }
+
+    #[test]
+    fn test_set_ops() {
+        let a = set(&[(0, 5), (10, 15)]);
+        let b = set(&[(3, 12), (14, 20)]);
+        assert_eq!(a.union(&b), set(&[(0, 20)]));
+        assert_eq!(a.intersection(&b), set(&[(3, 5), (10, 12), (14, 15)]));
+        assert_eq!(a.difference(&b), set(&[(0, 3), (12, 14)]));
+        assert_eq!(b.difference(&a), set(&[(5, 10), (15, 20)]));
+    }
+}
@@ -0,0 +1,141 @@
+use std::{
+    fmt::{Debug, Display},
+    str::FromStr,
+};
+
+use color_eyre::{
+    eyre::{ensure, eyre},
+    Report, Result,
+};
+
+/// A short identifier made of exactly `N` ASCII digits or uppercase letters, like `AAA` or `11Z`.
+///
+/// Labels are `Copy`, and can be packed into a dense index in `0..Label::<N>::COUNT` (reading the
+/// label as a base-36 number), which allows storing data about labels in a plain array rather
+/// than in a map.
+#[derive(Copy, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct Label<const N: usize>([u8; N]);
+
+impl<const N: usize> Label<N> {
+    /// Number of possible labels, i.e. the size of a table indexed by `index`.
+    pub const COUNT: usize = 36usize.pow(N as u32);
+
+    pub fn new(bytes: [u8; N]) -> Result<Self> {
+        for b in bytes {
+            ensure!(
+                b.is_ascii_digit() || b.is_ascii_uppercase(),
+                "Invalid label character {:?}",
+                b as char
+            );
+        }
+        Ok(Self(bytes))
+    }
+
+    pub fn as_str(&self) -> &str {
+        std::str::from_utf8(&self.0).expect("labels are ASCII")
+    }
+
+    /// Dense index of the label, in `0..Self::COUNT`.
+    pub fn index(&self) -> usize {
+        self.0.iter().fold(0, |idx, b| {
+            let digit = match b {
+                b'0'..=b'9' => b - b'0',
+                _ => b - b'A' + 10,
+            };
+            idx * 36 + digit as usize
+        })
+    }
+
+    /// Label with the given `index`, if it is in range.
+    pub fn from_index(mut index: usize) -> Option<Self> {
+        if index >= Self::COUNT {
+            return None;
+        }
+        let mut bytes = [0; N];
+        for b in bytes.iter_mut().rev() {
+            let digit = (index % 36) as u8;
+            *b = if digit < 10 {
+                b'0' + digit
+            } else {
+                b'A' + digit - 10
+            };
+            index /= 36;
+        }
+        Some(Self(bytes))
+    }
+
+    pub fn starts_with(&self, prefix: &str) -> bool {
+        self.as_str().starts_with(prefix)
+    }
+
+    pub fn ends_with(&self, suffix: &str) -> bool {
+        self.as_str().ends_with(suffix)
+    }
+}
+
+impl<const N: usize> FromStr for Label<N> {
+    type Err = Report;
+
+    fn from_str(s: &str) -> Result<Self> {
+        let bytes = s
+            .as_bytes()
+            .try_into()
+            .map_err(|_| eyre!("Label {s:?} should be {N} characters long"))?;
+        Self::new(bytes).map_err(|e| e.wrap_err(format!("Invalid label {s:?}")))
+    }
+}
+
+impl<const N: usize> TryFrom<&str> for Label<N> {
+    type Error = Report;
+
+    fn try_from(s: &str) -> Result<Self> {
+        s.parse()
+    }
+}
+
+impl<const N: usize> Display for Label<N> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.as_str())
+    }
+}
+
+impl<const N: usize> Debug for Label<N> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_tuple("Label").field(&self.as_str()).finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse() {
+        let label: Label<3> = "11Z".parse().unwrap();
+        assert_eq!(label.to_string(), "11Z");
+        assert_eq!(format!("{label:?}"), "Label(\"11Z\")");
+        assert!(label.starts_with("11"));
+        assert!(label.ends_with("Z"));
+        assert!(!label.ends_with("A"));
+
+        assert!("AAAA".parse::<Label<3>>().is_err());
+        assert!("AA".parse::<Label<3>>().is_err());
+        assert!("aaa".parse::<Label<3>>().is_err());
+        assert!("A-A".parse::<Label<3>>().is_err());
+    }
+
+    #[test]
+    fn test_index() {
+        assert_eq!(Label::<3>::COUNT, 46656);
+        assert_eq!("000".parse::<Label<3>>().unwrap().index(), 0);
+        assert_eq!("00Z".parse::<Label<3>>().unwrap().index(), 35);
+        assert_eq!("010".parse::<Label<3>>().unwrap().index(), 36);
+        assert_eq!("ZZZ".parse::<Label<3>>().unwrap().index(), 46655);
+        assert_eq!(Label::<3>::from_index(46656), None);
+        for index in [0, 1, 35, 36, 1000, 46655] {
+            let label = Label::<3>::from_index(index).unwrap();
+            assert_eq!(label.index(), index);
+            assert_eq!(label.as_str().parse::<Label<3>>().unwrap(), label);
+        }
+    }
+}
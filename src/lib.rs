@@ -1,9 +1,36 @@
-use color_eyre::{eyre::Context, Result};
+use color_eyre::{
+    eyre::{bail, Context},
+    Result,
+};
 use nu_ansi_term::{Color, Style};
-use num_enum::IntoPrimitive;
-use std::{collections::BTreeMap, fmt::Display};
+use num_enum::{IntoPrimitive, TryFromPrimitive};
+use std::{
+    collections::BTreeMap,
+    fmt::Display,
+    io::BufRead,
+    path::PathBuf,
+    time::{Duration, Instant},
+};
 
-#[derive(Copy, Clone, PartialEq, Eq, IntoPrimitive)]
+pub mod bitset;
+pub mod cache;
+pub mod cycle;
+pub mod graph;
+pub mod grid;
+pub mod interval;
+pub mod label;
+pub mod math;
+pub mod memo;
+pub mod parse;
+pub mod point;
+pub mod rng;
+pub mod scale;
+pub mod watch;
+
+use cache::{Cache, Key};
+use rng::Rng;
+
+#[derive(Copy, Clone, PartialEq, Eq, IntoPrimitive, TryFromPrimitive)]
 #[repr(u8)]
 pub enum Part {
     P1 = 1,
@@ -19,6 +46,21 @@ impl Display for Part {
 inventory::collect!(Day);
 
 type PartFn = fn(&str) -> Result<String>;
+/// Alternative entry point for days that can process their input incrementally
+type StreamFn = fn(&mut dyn BufRead) -> Result<String>;
+/// Generates a synthetic input of roughly the given size
+type GenFn = fn(usize, &mut Rng) -> String;
+/// Day-specific command, run on the puzzle input with extra arguments, returning text to print
+type ToolFn = fn(&str, &[String]) -> Result<String>;
+
+/// Outcome of running one part of a day, along with how long it took.
+pub struct PartResult {
+    pub part: Part,
+    pub answer: Result<String>,
+    pub elapsed: Duration,
+    /// Whether the answer was read from the cache rather than computed
+    pub cached: bool,
+}
 
 pub struct Day {
     /// Day number
@@ -27,11 +69,77 @@ pub struct Day {
     part1: PartFn,
     /// Part 2
     part2: PartFn,
+    /// Version of the solution, for days that opt into caching their answers
+    version: Option<&'static str>,
+    /// Streaming versions of part 1 and part 2, if available
+    streaming: Option<(StreamFn, StreamFn)>,
+    /// Synthetic input generator
+    generator: Option<GenFn>,
+    /// Named debugging tools
+    tools: &'static [(&'static str, ToolFn)],
 }
 
 impl Day {
     pub const fn new(n: usize, part1: PartFn, part2: PartFn) -> Self {
-        Self { n, part1, part2 }
+        Self {
+            n,
+            part1,
+            part2,
+            version: None,
+            streaming: None,
+            generator: None,
+            tools: &[],
+        }
+    }
+
+    /// Opt into the answer cache. The version must be bumped whenever a change to the solution
+    /// could change its answers.
+    pub const fn with_version(mut self, version: &'static str) -> Self {
+        self.version = Some(version);
+        self
+    }
+
+    /// Register streaming versions of both parts, used when reading from a pipe.
+    pub const fn with_streaming(mut self, part1: StreamFn, part2: StreamFn) -> Self {
+        self.streaming = Some((part1, part2));
+        self
+    }
+
+    /// Register a generator of synthetic inputs for stress-testing.
+    pub const fn with_generator(mut self, generator: GenFn) -> Self {
+        self.generator = Some(generator);
+        self
+    }
+
+    /// Register named tools, e.g. to explain how an answer was computed.
+    pub const fn with_tools(mut self, tools: &'static [(&'static str, ToolFn)]) -> Self {
+        self.tools = tools;
+        self
+    }
+
+    /// Generate a synthetic input of the given size, reproducible for a given seed.
+    pub fn generate(&self, size: usize, seed: u64) -> Option<String> {
+        self.generator
+            .map(|generator| generator(size, &mut Rng::new(seed)))
+    }
+
+    pub fn n(&self) -> usize {
+        self.n
+    }
+
+    /// Path of the puzzle input for this day.
+    pub fn input_path(&self) -> PathBuf {
+        PathBuf::from(format!("inputs/day{:02}.txt", self.n))
+    }
+
+    /// Path of the (optional) example input for this day.
+    pub fn example_path(&self) -> PathBuf {
+        PathBuf::from(format!("inputs/day{:02}.example.txt", self.n))
+    }
+
+    /// Path of the source file implementing this day.
+    pub fn source_path(&self) -> PathBuf {
+        PathBuf::from(format!("src/day{:02}.rs", self.n))
     }
 
     fn log_day(&self) {
@@ -42,17 +150,31 @@ impl Day {
         println!("{}", Color::Purple.underline().paint(s));
     }
 
-    fn log_part_result(&self, part: Part, res: Result<String>) {
-        let (color, msg) = match res {
-            Ok(s) => (Color::Green, s),
+    fn log_part_result(&self, res: &PartResult) {
+        let (color, msg) = match &res.answer {
+            Ok(s) => (Color::Green, s.clone()),
             Err(e) => (Color::Red, e.to_string()),
         };
-        println!(" → {}: {msg}", color.paint(format!("Part {part}")));
+        let timing = if res.cached {
+            "(cached)".to_string()
+        } else {
+            format!("({:.2?})", res.elapsed)
+        };
+        println!(
+            " → {}: {msg} {}",
+            color.paint(format!("Part {}", res.part)),
+            Color::DarkGray.paint(timing)
+        );
+    }
+
+    fn log_results(&self, results: &[PartResult]) {
+        for res in results {
+            self.log_part_result(res);
+        }
     }
 
     fn load_input(&self) -> Result<String> {
-        std::fs::read_to_string(format!("inputs/day{:02}.txt", self.n))
-            .wrap_err("Failed to load input file")
+        std::fs::read_to_string(self.input_path()).wrap_err("Failed to load input file")
     }
 
     fn part(&self, part: Part, input: &str) -> Result<String> {
@@ -62,28 +184,126 @@ impl Day {
         }
     }
 
-    fn run_part(&self, part: Part, input: &str) {
-        let res = self.part(part, input);
-        self.log_part_result(part, res);
+    fn solve(&self, part: Part, input: &str) -> PartResult {
+        let start = Instant::now();
+        let answer = self.part(part, input);
+        PartResult {
+            part,
+            answer,
+            elapsed: start.elapsed(),
+            cached: false,
+        }
+    }
+
+    fn solve_cached(&self, part: Part, input: &str, cache: &mut Cache) -> PartResult {
+        let Some(version) = self.version else {
+            return self.solve(part, input);
+        };
+
+        let key = Key::new(self.n, part, version, input);
+        if let Some(answer) = cache.get(&key) {
+            return PartResult {
+                part,
+                answer: Ok(answer.to_owned()),
+                elapsed: Duration::ZERO,
+                cached: true,
+            };
+        }
+
+        let res = self.solve(part, input);
+        if let Ok(answer) = &res.answer {
+            cache.insert(key, answer);
+        }
+        res
+    }
+
+    fn solve_parts(&self, part: Option<Part>, input: &str) -> Vec<PartResult> {
+        match part {
+            Some(part) => vec![self.solve(part, input)],
+            None => vec![self.solve(Part::P1, input), self.solve(Part::P2, input)],
+        }
     }
 
-    pub fn run(&self, part: impl Into<Option<Part>>) -> Result<()> {
+    /// Run one part on input read from `reader`. Days without a streaming implementation have
+    /// their whole input buffered first.
+    pub fn run_stream(&self, part: Part, reader: &mut dyn BufRead) -> Result<PartResult> {
         self.log_day();
-        let input = self.load_input()?;
-        match part.into() {
-            Some(part) => self.run_part(part, &input),
+        let res = match self.streaming {
+            Some((part1, part2)) => {
+                let start = Instant::now();
+                let answer = match part {
+                    Part::P1 => part1(reader),
+                    Part::P2 => part2(reader),
+                };
+                PartResult {
+                    part,
+                    answer,
+                    elapsed: start.elapsed(),
+                    cached: false,
+                }
+            }
             None => {
-                self.run_part(Part::P1, &input);
-                self.run_part(Part::P2, &input);
+                let mut input = String::new();
+                reader
+                    .read_to_string(&mut input)
+                    .wrap_err("Failed to read input")?;
+                self.solve(part, &input)
             }
-        }
+        };
+        self.log_part_result(&res);
 
+        Ok(res)
+    }
+
+    /// Run the tool called `name` on the puzzle input.
+    pub fn run_tool(&self, name: &str, args: &[String]) -> Result<()> {
+        let Some((_, tool)) = self.tools.iter().find(|(tool, _)| *tool == name) else {
+            let available = self.tools.iter().map(|(tool, _)| *tool).collect::<Vec<_>>();
+            bail!(
+                "Day {:02} has no {name:?} tool (available: {})",
+                self.n,
+                if available.is_empty() {
+                    "none".to_string()
+                } else {
+                    available.join(", ")
+                }
+            );
+        };
+        let input = self.load_input()?;
+        print!("{}", tool(&input, args)?);
         Ok(())
     }
+
+    pub fn run(
+        &self,
+        part: impl Into<Option<Part>>,
+        mut cache: Option<&mut Cache>,
+    ) -> Result<Vec<PartResult>> {
+        self.log_day();
+        let input = self.load_input()?;
+        let parts = match part.into() {
+            Some(part) => vec![part],
+            None => vec![Part::P1, Part::P2],
+        };
+        let results = parts
+            .into_iter()
+            .map(|part| match cache.as_deref_mut() {
+                Some(cache) => self.solve_cached(part, &input, cache),
+                None => self.solve(part, &input),
+            })
+            .collect::<Vec<_>>();
+        self.log_results(&results);
+
+        Ok(results)
+    }
 }
 
 #[derive(Default)]
-pub struct AoC(BTreeMap<usize, &'static Day>);
+pub struct AoC {
+    days: BTreeMap<usize, &'static Day>,
+    cache: Option<Cache>,
+}
+
 impl AoC {
     // pub fn register(&mut self, n: usize, day: impl Day + 'static) {
     //     self.0.insert(n, Runner::new(n, day));
@@ -93,23 +313,87 @@ impl AoC {
         for day in inventory::iter::<Day> {
             days.insert(day.n, day);
         }
-        Self(days)
+        Self { days, cache: None }
+    }
+
+    /// Use `cache` for the days that opted into caching their answers.
+    pub fn with_cache(mut self, cache: Cache) -> Self {
+        self.cache = Some(cache);
+        self
+    }
+
+    pub fn run_day(&mut self, n: usize) -> Result<()> {
+        if let Some(day) = self.days.get(&n) {
+            day.run(None, self.cache.as_mut())?;
+        } else {
+            println!("Day {:02} not implemented yet!", n);
+        }
+        self.save_cache()
+    }
+
+    pub fn run_all_days(&mut self) -> Result<()> {
+        for day in self.days.values() {
+            day.run(None, self.cache.as_mut())?;
+        }
+
+        self.save_cache()
+    }
+
+    fn save_cache(&mut self) -> Result<()> {
+        match &mut self.cache {
+            Some(cache) => cache.save(),
+            None => Ok(()),
+        }
+    }
+
+    pub fn stream_day(&self, n: usize, part: Part, reader: &mut dyn BufRead) -> Result<()> {
+        if let Some(day) = self.days.get(&n) {
+            day.run_stream(part, reader)?;
+        } else {
+            println!("Day {:02} not implemented yet!", n);
+        }
+        Ok(())
+    }
+
+    pub fn generate_day(&self, n: usize, size: usize, seed: u64) -> Result<()> {
+        match self.days.get(&n).map(|day| day.generate(size, seed)) {
+            Some(Some(input)) => print!("{input}"),
+            Some(None) => println!("Day {:02} has no input generator!", n),
+            None => println!("Day {:02} not implemented yet!", n),
+        }
+        Ok(())
     }
 
-    pub fn run_day(&self, n: usize) -> Result<()> {
-        if let Some(day) = self.0.get(&n) {
-            day.run(None)?;
+    pub fn scale_day(&self, n: usize, part: Option<Part>) -> Result<()> {
+        if let Some(day) = self.days.get(&n) {
+            match part {
+                Some(part) => day.scale(part)?,
+                None => {
+                    day.scale(Part::P1)?;
+                    day.scale(Part::P2)?;
+                }
+            }
         } else {
             println!("Day {:02} not implemented yet!", n);
         }
         Ok(())
     }
 
-    pub fn run_all_days(&self) -> Result<()> {
-        for day in self.0.values() {
-            day.run(None)?;
+    pub fn tool_day(&self, n: usize, name: &str, args: &[String]) -> Result<()> {
+        if let Some(day) = self.days.get(&n) {
+            day.run_tool(name, args)?;
+        } else {
+            println!("Day {:02} not implemented yet!", n);
         }
+        Ok(())
+    }
 
+    pub fn watch_day(&self, n: usize) -> Result<()> {
+        if let Some(day) = self.days.get(&n) {
+            day.watch()?;
+        } else {
+            println!("Day {:02} not implemented yet!", n);
+        }
         Ok(())
     }
 }
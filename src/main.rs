@@ -1,5 +1,10 @@
-use aoc2023::AoC;
-use color_eyre::Result;
+use std::{fs::File, io::BufReader};
+
+use aoc2023::{cache::Cache, AoC, Part};
+use color_eyre::{
+    eyre::{bail, Context},
+    Result,
+};
 
 mod day01;
 mod day02;
@@ -11,19 +16,72 @@ mod day07;
 mod day08;
 mod day09;
 
+const USAGE: &str = "\
+Usage: aoc2023 [--no-cache] [<day>]
+       aoc2023 [--no-cache] watch <day>
+       aoc2023 generate <day> <size> [<seed>]
+       aoc2023 scale <day> [<part>]
+       aoc2023 tool <day> <tool> [<args>...]
+       aoc2023 stream <day> <part> [<path>|-]";
+
 fn main() -> Result<()> {
     color_eyre::install()?;
 
-    let aoc = AoC::new();
+    let (flags, args): (Vec<_>, Vec<_>) =
+        std::env::args().skip(1).partition(|a| a.starts_with("--"));
+
+    if let Some(flag) = flags.iter().find(|f| *f != "--no-cache") {
+        bail!("Unknown flag {flag:?}\n\n{USAGE}");
+    }
+
+    let mut cache = Cache::load_or_empty(aoc2023::cache::DEFAULT_PATH);
+    if flags.iter().any(|f| f == "--no-cache") {
+        cache = cache.force_recompute();
+    }
+    let mut aoc = AoC::new().with_cache(cache);
 
-    if let Some(day) = std::env::args()
-        .nth(1)
-        .and_then(|n| n.parse::<usize>().ok())
-    {
-        aoc.run_day(day)?;
-    } else {
-        aoc.run_all_days()?;
+    match args.as_slice() {
+        [cmd, day] if cmd == "watch" => {
+            aoc.watch_day(parse_day(day)?)?;
+        }
+        [cmd, day, size, seed @ ..] if cmd == "generate" && seed.len() <= 1 => {
+            let size = size.parse().wrap_err("Invalid size")?;
+            let seed = match seed.first() {
+                Some(seed) => seed.parse().wrap_err("Invalid seed")?,
+                None => 0,
+            };
+            aoc.generate_day(parse_day(day)?, size, seed)?;
+        }
+        [cmd, day, part @ ..] if cmd == "scale" && part.len() <= 1 => {
+            let part = part.first().map(|p| parse_part(p)).transpose()?;
+            aoc.scale_day(parse_day(day)?, part)?;
+        }
+        [cmd, day, tool, args @ ..] if cmd == "tool" => {
+            aoc.tool_day(parse_day(day)?, tool, args)?;
+        }
+        [cmd, day, part, path @ ..] if cmd == "stream" && path.len() <= 1 => {
+            let (day, part) = (parse_day(day)?, parse_part(part)?);
+            match path.first().map(String::as_str) {
+                None | Some("-") => aoc.stream_day(day, part, &mut std::io::stdin().lock())?,
+                Some(path) => {
+                    let file = File::open(path).wrap_err("Failed to open input file")?;
+                    aoc.stream_day(day, part, &mut BufReader::new(file))?
+                }
+            }
+        }
+        [day] => aoc.run_day(parse_day(day)?)?,
+        [] => aoc.run_all_days()?,
+        _ => bail!("Invalid arguments {args:?}\n\n{USAGE}"),
     }
 
     Ok(())
 }
+
+fn parse_day(s: &str) -> Result<usize> {
+    s.parse().wrap_err("Invalid day number")
+}
+
+fn parse_part(s: &str) -> Result<Part> {
+    let n = s.parse::<u8>().wrap_err("Invalid part number")?;
+    Part::try_from(n).wrap_err("Invalid part number")
+}
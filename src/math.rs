@@ -0,0 +1,138 @@
+//! Number theory helpers.
+//!
+//! Everything that can overflow returns an `Option` rather than panicking or wrapping around.
+
+use gcd::Gcd;
+
+pub fn gcd(a: u128, b: u128) -> u128 {
+    a.gcd(b)
+}
+
+/// Least common multiple of `a` and `b`, or `None` on overflow.
+pub fn lcm(a: u128, b: u128) -> Option<u128> {
+    if a == 0 || b == 0 {
+        return Some(0);
+    }
+    (a / gcd(a, b)).checked_mul(b)
+}
+
+/// Greatest common divisor of all the values, 0 if there are none.
+pub fn gcd_all(values: &[u128]) -> u128 {
+    values.iter().fold(0, |acc, v| gcd(acc, *v))
+}
+
+/// Least common multiple of all the values, 1 if there are none, or `None` on overflow.
+pub fn lcm_all(values: &[u128]) -> Option<u128> {
+    values.iter().try_fold(1, |acc, v| lcm(acc, *v))
+}
+
+/// Extended Euclidean algorithm: returns `(g, x, y)` such that `a * x + b * y = g`, with `g` the
+/// (non-negative) greatest common divisor of `a` and `b`.
+pub fn extended_gcd(a: i128, b: i128) -> (i128, i128, i128) {
+    let (mut old_r, mut r) = (a, b);
+    let (mut old_x, mut x) = (1, 0);
+    let (mut old_y, mut y) = (0, 1);
+    while r != 0 {
+        let q = old_r / r;
+        (old_r, r) = (r, old_r - q * r);
+        (old_x, x) = (x, old_x - q * x);
+        (old_y, y) = (y, old_y - q * y);
+    }
+    if old_r < 0 {
+        (-old_r, -old_x, -old_y)
+    } else {
+        (old_r, old_x, old_y)
+    }
+}
+
+/// Inverse of `a` modulo `m`, in `0..m`, if `a` and `m` are coprime.
+pub fn mod_inv(a: i128, m: i128) -> Option<i128> {
+    assert!(m > 0, "modulus must be positive");
+    let (g, x, _) = extended_gcd(a.rem_euclid(m), m);
+    (g == 1).then(|| x.rem_euclid(m))
+}
+
+/// `base^exp mod m`, by repeated squaring.
+pub fn mod_pow(base: u64, mut exp: u64, m: u64) -> u64 {
+    assert!(m > 0, "modulus must be positive");
+    let m = m as u128;
+    let mut base = base as u128 % m;
+    let mut result = 1 % m;
+    while exp > 0 {
+        if exp & 1 == 1 {
+            result = result * base % m;
+        }
+        base = base * base % m;
+        exp >>= 1;
+    }
+    result as u64
+}
+
+/// Chinese Remainder Theorem: solve the system of congruences `x ≡ r (mod m)` for each `(r, m)`.
+///
+/// The moduli don't need to be coprime. Returns `(x, l)` where `l` is the least common multiple of
+/// the moduli and `x` the unique solution in `0..l`, or `None` if the system has no solution (or
+/// the computation would overflow).
+pub fn crt(congruences: &[(i128, i128)]) -> Option<(i128, i128)> {
+    congruences.iter().try_fold((0, 1), |(r1, m1), &(r2, m2)| {
+        assert!(m2 > 0, "modulus must be positive");
+        let r2 = r2.rem_euclid(m2);
+        let (g, p, _) = extended_gcd(m1, m2);
+        let diff = r2 - r1;
+        if diff % g != 0 {
+            return None;
+        }
+        // m1 * p ≡ g (mod m2), so x = r1 + m1 * p * diff / g satisfies both congruences
+        let m2_g = m2 / g;
+        let k = ((diff / g) % m2_g).checked_mul(p % m2_g)?.rem_euclid(m2_g);
+        let l = m1.checked_mul(m2_g)?;
+        let x = m1.checked_mul(k)?.checked_add(r1)?.rem_euclid(l);
+        Some((x, l))
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_gcd_lcm() {
+        assert_eq!(gcd_all(&[12, 18, 30]), 6);
+        assert_eq!(gcd_all(&[]), 0);
+        assert_eq!(lcm_all(&[4, 6, 10]), Some(60));
+        assert_eq!(lcm_all(&[]), Some(1));
+        assert_eq!(lcm(0, 5), Some(0));
+        assert_eq!(lcm(u128::MAX, u128::MAX - 1), None);
+        // a * b would overflow, but the lcm doesn't
+        assert_eq!(lcm(1 << 100, 1 << 101), Some(1 << 101));
+    }
+
+    #[test]
+    fn test_extended_gcd() {
+        for (a, b) in [(240, 46), (-240, 46), (17, 0), (0, -5), (7, 13)] {
+            let (g, x, y) = extended_gcd(a, b);
+            assert_eq!(g, gcd(a.unsigned_abs(), b.unsigned_abs()) as i128);
+            assert_eq!(a * x + b * y, g);
+        }
+    }
+
+    #[test]
+    fn test_mod_inv_pow() {
+        assert_eq!(mod_inv(3, 11), Some(4));
+        assert_eq!(mod_inv(-3, 11), Some(7));
+        assert_eq!(mod_inv(6, 9), None);
+        assert_eq!(mod_pow(4, 13, 497), 445);
+        assert_eq!(mod_pow(2, 0, 1), 0);
+        assert_eq!(mod_pow(u64::MAX, u64::MAX, u64::MAX - 1), 1);
+    }
+
+    #[test]
+    fn test_crt() {
+        assert_eq!(crt(&[(2, 3), (3, 5), (2, 7)]), Some((23, 105)));
+        // Non-coprime moduli
+        assert_eq!(crt(&[(2, 4), (4, 6)]), Some((10, 12)));
+        assert_eq!(crt(&[(1, 4), (2, 6)]), None);
+        assert_eq!(crt(&[(-1, 5)]), Some((4, 5)));
+        assert_eq!(crt(&[]), Some((0, 1)));
+    }
+}
@@ -0,0 +1,214 @@
+//! Memoization tables.
+//!
+//! A `Memo` is meant to be created for a single run and passed around explicitly, rather than
+//! living in a global. `SyncMemo` is the same table behind a lock, for when it has to be shared
+//! between threads.
+
+use std::{collections::HashMap, fmt::Display, hash::Hash, sync::Mutex};
+
+/// Hit/miss statistics of a memo table.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct Stats {
+    pub hits: u64,
+    pub misses: u64,
+}
+
+impl Stats {
+    /// Fraction of the lookups that were answered from the table.
+    pub fn hit_rate(&self) -> f64 {
+        let total = self.hits + self.misses;
+        if total == 0 {
+            0.
+        } else {
+            self.hits as f64 / total as f64
+        }
+    }
+}
+
+impl Display for Stats {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{} hits, {} misses ({:.1}% hit rate)",
+            self.hits,
+            self.misses,
+            100. * self.hit_rate()
+        )
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Memo<K, V> {
+    values: HashMap<K, V>,
+    stats: Stats,
+}
+
+impl<K, V> Default for Memo<K, V> {
+    fn default() -> Self {
+        Self {
+            values: HashMap::new(),
+            stats: Stats::default(),
+        }
+    }
+}
+
+impl<K, V> Memo<K, V>
+where
+    K: Eq + Hash,
+    V: Clone,
+{
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn len(&self) -> usize {
+        self.values.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.values.is_empty()
+    }
+
+    pub fn stats(&self) -> Stats {
+        self.stats
+    }
+
+    /// Forget all the values, and reset the statistics.
+    pub fn clear(&mut self) {
+        self.values.clear();
+        self.stats = Stats::default();
+    }
+
+    /// Value for `key`, computing it with `f` if it isn't known yet.
+    pub fn get_or_insert_with(&mut self, key: K, f: impl FnOnce(&K) -> V) -> V {
+        self.memoize(key, |_, key| f(key))
+    }
+
+    /// Value for `key`, computing it with `f` if it isn't known yet. `f` gets the table itself, so
+    /// that a recursive function can memoize its recursive calls:
+    ///
+    /// ```
+    /// # use aoc2023::memo::Memo;
+    /// fn fib(memo: &mut Memo<u64, u64>, n: u64) -> u64 {
+    ///     memo.memoize(n, |memo, &n| if n < 2 { n } else { fib(memo, n - 1) + fib(memo, n - 2) })
+    /// }
+    /// assert_eq!(fib(&mut Memo::new(), 90), 2880067194370816120);
+    /// ```
+    pub fn memoize(&mut self, key: K, f: impl FnOnce(&mut Self, &K) -> V) -> V {
+        if let Some(v) = self.values.get(&key) {
+            self.stats.hits += 1;
+            return v.clone();
+        }
+        self.stats.misses += 1;
+        let v = f(self, &key);
+        self.values.insert(key, v.clone());
+        v
+    }
+}
+
+/// A `Memo` that can be shared between threads.
+///
+/// The lock is not held while computing a value, so `f` may use the table too. Two threads
+/// missing on the same key at the same time both compute the value.
+#[derive(Debug, Default)]
+pub struct SyncMemo<K, V>(Mutex<Memo<K, V>>);
+
+impl<K, V> SyncMemo<K, V>
+where
+    K: Eq + Hash,
+    V: Clone,
+{
+    pub fn new() -> Self {
+        Self(Mutex::new(Memo::new()))
+    }
+
+    pub fn stats(&self) -> Stats {
+        self.lock().stats()
+    }
+
+    /// Value for `key`, computing it with `f` if it isn't known yet.
+    pub fn get_or_insert_with(&self, key: K, f: impl FnOnce(&K) -> V) -> V {
+        {
+            let mut memo = self.lock();
+            if let Some(v) = memo.values.get(&key) {
+                let v = v.clone();
+                memo.stats.hits += 1;
+                return v;
+            }
+            memo.stats.misses += 1;
+        }
+        let v = f(&key);
+        self.lock().values.insert(key, v.clone());
+        v
+    }
+
+    pub fn into_inner(self) -> Memo<K, V> {
+        self.0.into_inner().unwrap_or_else(|e| e.into_inner())
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, Memo<K, V>> {
+        // The table is always left consistent, so a panic in another thread doesn't matter
+        self.0.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_memo() {
+        let mut memo = Memo::new();
+        let mut calls = 0;
+        for n in [1, 2, 1, 1] {
+            memo.get_or_insert_with(n, |n| {
+                calls += 1;
+                n * 10
+            });
+        }
+        assert_eq!(calls, 2);
+        assert_eq!(memo.len(), 2);
+        assert_eq!(memo.stats(), Stats { hits: 2, misses: 2 });
+        assert_eq!(
+            memo.stats().to_string(),
+            "2 hits, 2 misses (50.0% hit rate)"
+        );
+        memo.clear();
+        assert!(memo.is_empty());
+        assert_eq!(memo.stats(), Stats::default());
+    }
+
+    #[test]
+    fn test_recursive() {
+        fn paths(memo: &mut Memo<(u32, u32), u64>, x: u32, y: u32) -> u64 {
+            memo.memoize((x, y), |memo, &(x, y)| {
+                if x == 0 || y == 0 {
+                    1
+                } else {
+                    paths(memo, x - 1, y) + paths(memo, x, y - 1)
+                }
+            })
+        }
+
+        let mut memo = Memo::new();
+        assert_eq!(paths(&mut memo, 16, 16), 601080390);
+        assert_eq!(memo.len(), 17 * 17 - 1);
+    }
+
+    #[test]
+    fn test_sync() {
+        let memo = SyncMemo::new();
+        std::thread::scope(|s| {
+            for _ in 0..4 {
+                s.spawn(|| {
+                    for n in 0..100u64 {
+                        assert_eq!(memo.get_or_insert_with(n, |n| n * n), n * n);
+                    }
+                });
+            }
+        });
+        let stats = memo.stats();
+        assert_eq!(stats.hits + stats.misses, 400);
+        assert_eq!(memo.into_inner().len(), 100);
+    }
+}
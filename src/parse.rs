@@ -0,0 +1,152 @@
+//! Parsing helpers built on nom.
+//!
+//! The combinators cover the pieces most inputs are made of, and the `parse_*` functions run a
+//! parser on a whole line, turning nom errors (and leftover input) into eyre errors that mention
+//! the line number.
+
+use std::io::BufRead;
+
+use color_eyre::{
+    eyre::{ensure, eyre, WrapErr},
+    Report, Result,
+};
+use nom::{
+    bytes::complete::tag,
+    character::complete::{space0, space1, u32},
+    error::Error,
+    multi::separated_list1,
+    sequence::{pair, preceded, terminated},
+    Finish, IResult, Parser,
+};
+
+/// Numbers separated by spaces, e.g. `numbers(u32)` for `41 48  83`.
+pub fn numbers<'a, T>(
+    number: impl Parser<&'a str, T, Error<&'a str>>,
+) -> impl FnMut(&'a str) -> IResult<&'a str, Vec<T>> {
+    separated_list1(space1, number)
+}
+
+/// A `Name N:` prefix, e.g. `header("Card")` for `Card  12: `, returning `N`. Spaces after the
+/// colon are skipped.
+pub fn header<'a>(name: &'static str) -> impl FnMut(&'a str) -> IResult<&'a str, u32> {
+    terminated(
+        preceded(pair(tag(name), space1), u32),
+        pair(tag(":"), space0),
+    )
+}
+
+/// Lines of `input`, with their 1-based line number.
+pub fn numbered_lines(input: &str) -> impl Iterator<Item = (usize, &str)> {
+    input.lines().enumerate().map(|(idx, line)| (idx + 1, line))
+}
+
+/// Groups of lines separated by blank lines, with their 1-based line number.
+pub fn sections(input: &str) -> Vec<Vec<(usize, &str)>> {
+    let mut sections = vec![];
+    let mut current = vec![];
+    for (n, line) in numbered_lines(input) {
+        if line.trim().is_empty() {
+            if !current.is_empty() {
+                sections.push(std::mem::take(&mut current));
+            }
+        } else {
+            current.push((n, line));
+        }
+    }
+    if !current.is_empty() {
+        sections.push(current);
+    }
+    sections
+}
+
+/// Run `parser` on the whole of `input`.
+pub fn parse_all<'a, T>(
+    parser: &mut impl Parser<&'a str, T, Error<&'a str>>,
+    input: &'a str,
+) -> Result<T> {
+    let (rest, value) = parser.parse(input).finish().map_err(to_report)?;
+    ensure!(rest.is_empty(), "Unexpected trailing input {rest:?}");
+    Ok(value)
+}
+
+/// Run `parser` on `line`, the `n`th line of the input.
+pub fn parse_line<'a, T>(
+    parser: &mut impl Parser<&'a str, T, Error<&'a str>>,
+    line: &'a str,
+    n: usize,
+) -> Result<T> {
+    parse_all(parser, line).wrap_err_with(|| format!("Invalid line {n}: {line:?}"))
+}
+
+/// Run `parser` on each of the numbered `lines`, keeping the line numbers along with the results.
+pub fn parse_lines<'a, T>(
+    lines: impl IntoIterator<Item = (usize, &'a str)>,
+    mut parser: impl Parser<&'a str, T, Error<&'a str>>,
+) -> Result<Vec<(usize, T)>> {
+    lines
+        .into_iter()
+        .map(|(n, line)| Ok((n, parse_line(&mut parser, line, n)?)))
+        .collect()
+}
+
+/// Parse each line read from `input` with `parser`.
+pub fn read_lines<'r, T: 'r>(
+    input: &'r mut dyn BufRead,
+    mut parser: fn(&str) -> IResult<&str, T>,
+) -> impl Iterator<Item = Result<T>> + 'r {
+    input.lines().enumerate().map(move |(idx, line)| {
+        let line = line.wrap_err("Failed to read input")?;
+        parse_line(&mut parser, &line, idx + 1)
+    })
+}
+
+fn to_report(e: Error<&str>) -> Report {
+    eyre!("Expected {} at {:?}", e.code.description(), e.input)
+}
+
+#[cfg(test)]
+mod tests {
+    use nom::character::complete::i64;
+
+    use super::*;
+
+    fn parse_numbers(input: &str) -> IResult<&str, Vec<i64>> {
+        numbers(i64)(input)
+    }
+
+    #[test]
+    fn test_combinators() {
+        assert_eq!(numbers(i64)("1  -2 3|"), Ok(("|", vec![1, -2, 3])));
+        assert_eq!(header("Card")("Card  12:  4"), Ok(("4", 12)));
+        assert!(header("Card")("Game 1: 4").is_err());
+    }
+
+    #[test]
+    fn test_sections() {
+        let input = "a\nb\n\n\nc\n  \nd\n";
+        assert_eq!(
+            sections(input),
+            vec![vec![(1, "a"), (2, "b")], vec![(5, "c")], vec![(7, "d")]]
+        );
+    }
+
+    #[test]
+    fn test_parse_lines() {
+        assert_eq!(
+            parse_lines(numbered_lines("1 2\n3"), numbers(i64)).unwrap(),
+            vec![(1, vec![1, 2]), (2, vec![3])]
+        );
+
+        let err = parse_lines(numbered_lines("1 2\n3 x"), numbers(i64)).unwrap_err();
+        assert_eq!(err.to_string(), "Invalid line 2: \"3 x\"");
+        assert_eq!(
+            err.root_cause().to_string(),
+            "Unexpected trailing input \" x\""
+        );
+
+        let mut input = "1\nx\n".as_bytes();
+        let res = read_lines(&mut input, parse_numbers).collect::<Vec<_>>();
+        assert_eq!(res[0].as_ref().unwrap(), &vec![1]);
+        assert!(res[1].is_err());
+    }
+}
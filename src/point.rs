@@ -0,0 +1,327 @@
+use std::{
+    fmt::Display,
+    ops::{Add, AddAssign, Mul, Neg, Sub},
+};
+
+/// A displacement between two points.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct Vec2 {
+    pub x: i64,
+    pub y: i64,
+}
+
+/// A point on a 2D plane that extends in every direction.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct Point {
+    pub x: i64,
+    pub y: i64,
+}
+
+/// A point with non-negative coordinates, typically a position in a grid.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct UPoint {
+    pub x: usize,
+    pub y: usize,
+}
+
+/// Inclusive bounding box.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Rect {
+    pub min: Point,
+    pub max: Point,
+}
+
+/// Compass directions. As is usual for puzzles, `y` grows towards the south.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum Direction {
+    N,
+    NE,
+    E,
+    SE,
+    S,
+    SW,
+    W,
+    NW,
+}
+
+impl Vec2 {
+    pub const fn new(x: i64, y: i64) -> Self {
+        Self { x, y }
+    }
+
+    pub fn manhattan(&self) -> u64 {
+        self.x.unsigned_abs() + self.y.unsigned_abs()
+    }
+
+    pub fn chebyshev(&self) -> u64 {
+        self.x.unsigned_abs().max(self.y.unsigned_abs())
+    }
+}
+
+impl Point {
+    pub const fn new(x: i64, y: i64) -> Self {
+        Self { x, y }
+    }
+
+    pub fn step(&self, dir: Direction) -> Self {
+        *self + dir.to_vec()
+    }
+
+    /// The neighbouring point in direction `dir`, if it is inside `bounds`.
+    pub fn step_within(&self, dir: Direction, bounds: &Rect) -> Option<Self> {
+        let p = self.step(dir);
+        bounds.contains(&p).then_some(p)
+    }
+
+    pub fn manhattan(&self, other: &Self) -> u64 {
+        (*other - *self).manhattan()
+    }
+
+    pub fn chebyshev(&self, other: &Self) -> u64 {
+        (*other - *self).chebyshev()
+    }
+}
+
+impl UPoint {
+    pub const fn new(x: usize, y: usize) -> Self {
+        Self { x, y }
+    }
+
+    /// `self + v`, unless that would make a coordinate negative.
+    pub fn checked_add(&self, v: Vec2) -> Option<Self> {
+        Some(Self {
+            x: self.x.checked_add_signed(v.x.try_into().ok()?)?,
+            y: self.y.checked_add_signed(v.y.try_into().ok()?)?,
+        })
+    }
+
+    /// The neighbouring point in direction `dir`, if it is inside a `width` x `height` area
+    /// starting at the origin.
+    pub fn step_within(&self, dir: Direction, width: usize, height: usize) -> Option<Self> {
+        self.checked_add(dir.to_vec())
+            .filter(|p| p.x < width && p.y < height)
+    }
+
+    pub fn manhattan(&self, other: &Self) -> usize {
+        self.x.abs_diff(other.x) + self.y.abs_diff(other.y)
+    }
+
+    pub fn chebyshev(&self, other: &Self) -> usize {
+        self.x.abs_diff(other.x).max(self.y.abs_diff(other.y))
+    }
+}
+
+impl Rect {
+    pub fn new(min: Point, max: Point) -> Self {
+        Self { min, max }
+    }
+
+    /// Smallest bounding box containing all the points, if there are any.
+    pub fn enclosing(points: impl IntoIterator<Item = Point>) -> Option<Self> {
+        points.into_iter().fold(None, |rect, p| {
+            Some(match rect {
+                None => Rect::new(p, p),
+                Some(Rect { min, max }) => Rect::new(
+                    Point::new(min.x.min(p.x), min.y.min(p.y)),
+                    Point::new(max.x.max(p.x), max.y.max(p.y)),
+                ),
+            })
+        })
+    }
+
+    pub fn contains(&self, p: &Point) -> bool {
+        (self.min.x..=self.max.x).contains(&p.x) && (self.min.y..=self.max.y).contains(&p.y)
+    }
+}
+
+impl Direction {
+    pub const ALL: [Self; 8] = [
+        Self::N,
+        Self::NE,
+        Self::E,
+        Self::SE,
+        Self::S,
+        Self::SW,
+        Self::W,
+        Self::NW,
+    ];
+
+    pub const CARDINAL: [Self; 4] = [Self::N, Self::E, Self::S, Self::W];
+
+    fn from_index(i: usize) -> Self {
+        Self::ALL[i % 8]
+    }
+
+    pub fn to_vec(&self) -> Vec2 {
+        match self {
+            Self::N => Vec2::new(0, -1),
+            Self::NE => Vec2::new(1, -1),
+            Self::E => Vec2::new(1, 0),
+            Self::SE => Vec2::new(1, 1),
+            Self::S => Vec2::new(0, 1),
+            Self::SW => Vec2::new(-1, 1),
+            Self::W => Vec2::new(-1, 0),
+            Self::NW => Vec2::new(-1, -1),
+        }
+    }
+
+    /// Turn 90° clockwise.
+    pub fn turn_right(&self) -> Self {
+        Self::from_index(*self as usize + 2)
+    }
+
+    /// Turn 90° counter-clockwise.
+    pub fn turn_left(&self) -> Self {
+        Self::from_index(*self as usize + 6)
+    }
+
+    /// Turn 45° clockwise.
+    pub fn turn_right45(&self) -> Self {
+        Self::from_index(*self as usize + 1)
+    }
+
+    /// Turn 45° counter-clockwise.
+    pub fn turn_left45(&self) -> Self {
+        Self::from_index(*self as usize + 7)
+    }
+
+    pub fn opposite(&self) -> Self {
+        Self::from_index(*self as usize + 4)
+    }
+
+    pub fn is_diagonal(&self) -> bool {
+        (*self as usize) % 2 == 1
+    }
+}
+
+impl Add for Vec2 {
+    type Output = Self;
+
+    fn add(self, rhs: Self) -> Self::Output {
+        Self::new(self.x + rhs.x, self.y + rhs.y)
+    }
+}
+
+impl Neg for Vec2 {
+    type Output = Self;
+
+    fn neg(self) -> Self::Output {
+        Self::new(-self.x, -self.y)
+    }
+}
+
+impl Mul<i64> for Vec2 {
+    type Output = Self;
+
+    fn mul(self, rhs: i64) -> Self::Output {
+        Self::new(self.x * rhs, self.y * rhs)
+    }
+}
+
+impl Add<Vec2> for Point {
+    type Output = Self;
+
+    fn add(self, rhs: Vec2) -> Self::Output {
+        Self::new(self.x + rhs.x, self.y + rhs.y)
+    }
+}
+
+impl AddAssign<Vec2> for Point {
+    fn add_assign(&mut self, rhs: Vec2) {
+        *self = *self + rhs;
+    }
+}
+
+impl Sub<Vec2> for Point {
+    type Output = Self;
+
+    fn sub(self, rhs: Vec2) -> Self::Output {
+        self + -rhs
+    }
+}
+
+impl Sub for Point {
+    type Output = Vec2;
+
+    fn sub(self, rhs: Self) -> Self::Output {
+        Vec2::new(self.x - rhs.x, self.y - rhs.y)
+    }
+}
+
+impl From<(usize, usize)> for UPoint {
+    fn from((x, y): (usize, usize)) -> Self {
+        Self::new(x, y)
+    }
+}
+
+impl From<(i64, i64)> for Point {
+    fn from((x, y): (i64, i64)) -> Self {
+        Self::new(x, y)
+    }
+}
+
+impl From<UPoint> for Point {
+    fn from(p: UPoint) -> Self {
+        Self::new(p.x as i64, p.y as i64)
+    }
+}
+
+impl TryFrom<Point> for UPoint {
+    type Error = std::num::TryFromIntError;
+
+    fn try_from(p: Point) -> Result<Self, Self::Error> {
+        Ok(Self::new(p.x.try_into()?, p.y.try_into()?))
+    }
+}
+
+impl Display for Point {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "({}, {})", self.x, self.y)
+    }
+}
+
+impl Display for UPoint {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "({}, {})", self.x, self.y)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_turns() {
+        assert_eq!(Direction::N.turn_right(), Direction::E);
+        assert_eq!(Direction::N.turn_left(), Direction::W);
+        assert_eq!(Direction::NW.turn_right45(), Direction::N);
+        assert_eq!(Direction::SE.opposite(), Direction::NW);
+        for dir in Direction::ALL {
+            assert_eq!(dir.to_vec() + dir.opposite().to_vec(), Vec2::default());
+            assert_eq!(dir.turn_left().turn_right(), dir);
+        }
+    }
+
+    #[test]
+    fn test_distances() {
+        let (a, b) = (Point::new(1, -2), Point::new(-3, 4));
+        assert_eq!(a.manhattan(&b), 10);
+        assert_eq!(a.chebyshev(&b), 6);
+        assert_eq!(UPoint::new(1, 5).manhattan(&UPoint::new(4, 2)), 6);
+    }
+
+    #[test]
+    fn test_step_within() {
+        let p = UPoint::new(0, 1);
+        assert_eq!(p.step_within(Direction::W, 3, 3), None);
+        assert_eq!(p.step_within(Direction::NE, 3, 3), Some(UPoint::new(1, 0)));
+        assert_eq!(p.step_within(Direction::S, 3, 2), None);
+
+        let bounds = Rect::enclosing([Point::new(-1, -1), Point::new(1, 1)]).unwrap();
+        assert_eq!(
+            Point::new(0, 0).step_within(Direction::SW, &bounds),
+            Some(Point::new(-1, 1))
+        );
+        assert_eq!(Point::new(1, 0).step_within(Direction::E, &bounds), None);
+    }
+}
@@ -0,0 +1,143 @@
+use color_eyre::Result;
+
+/// Small, seedable pseudo-random number generator (SplitMix64).
+///
+/// This is only meant for generating puzzle inputs: it is fast, reproducible across platforms for
+/// a given seed, and has no dependencies. It is *not* suitable for anything security related.
+#[derive(Debug, Clone)]
+pub struct Rng(u64);
+
+impl Rng {
+    pub fn new(seed: u64) -> Self {
+        Self(seed)
+    }
+
+    pub fn next_u64(&mut self) -> u64 {
+        self.0 = self.0.wrapping_add(0x9e3779b97f4a7c15);
+        let mut z = self.0;
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58476d1ce4e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d049bb133111eb);
+        z ^ (z >> 31)
+    }
+
+    /// Uniformly distributed number in `0..n`.
+    pub fn below(&mut self, n: u64) -> u64 {
+        assert!(n > 0, "empty range");
+        ((self.next_u64() as u128 * n as u128) >> 64) as u64
+    }
+
+    /// Uniformly distributed number in `lo..=hi`.
+    pub fn between(&mut self, lo: u64, hi: u64) -> u64 {
+        assert!(lo <= hi, "empty range");
+        match (hi - lo).checked_add(1) {
+            Some(n) => lo + self.below(n),
+            None => self.next_u64(),
+        }
+    }
+
+    /// Uniformly distributed signed number in `lo..=hi`.
+    pub fn between_i64(&mut self, lo: i64, hi: i64) -> i64 {
+        assert!(lo <= hi, "empty range");
+        lo.wrapping_add(self.between(0, hi.abs_diff(lo)) as i64)
+    }
+
+    /// Returns `true` with probability `p`.
+    pub fn chance(&mut self, p: f64) -> bool {
+        ((self.next_u64() >> 11) as f64 / (1u64 << 53) as f64) < p
+    }
+
+    pub fn choose<'a, T>(&mut self, items: &'a [T]) -> &'a T {
+        &items[self.below(items.len() as u64) as usize]
+    }
+
+    pub fn shuffle<T>(&mut self, items: &mut [T]) {
+        for i in (1..items.len()).rev() {
+            let j = self.below(i as u64 + 1) as usize;
+            items.swap(i, j);
+        }
+    }
+}
+
+/// Check the properties every input generator should have, panicking if one doesn't hold:
+/// - the same seed always gives the same input, and different seeds give different inputs;
+/// - the input round-trips through the day's parser: `measure` parses it and returns its actual
+///   size (e.g. the number of games), in the same unit as the size requested from `generate`;
+/// - that size grows with the requested one, `sizes` being given in increasing order.
+pub fn check_generator(
+    generate: fn(usize, &mut Rng) -> String,
+    sizes: &[usize],
+    measure: impl Fn(&str) -> Result<usize>,
+) {
+    let mut last: Option<(usize, usize)> = None;
+    for &size in sizes {
+        let mut measured = vec![];
+        for seed in 0..5 {
+            let input = generate(size, &mut Rng::new(seed));
+            assert_eq!(
+                input,
+                generate(size, &mut Rng::new(seed)),
+                "size {size}, seed {seed} gives different inputs"
+            );
+            assert_ne!(
+                input,
+                generate(size, &mut Rng::new(seed + 100)),
+                "size {size}, seeds {seed} and {} give the same input",
+                seed + 100
+            );
+            let n = measure(&input).unwrap_or_else(|e| {
+                panic!("size {size}, seed {seed} gives an invalid input: {e:?}\n{input}")
+            });
+            measured.push(n);
+        }
+        let smallest = *measured.iter().min().expect("some seeds");
+        if let Some((last_size, largest)) = last {
+            assert!(
+                smallest > largest,
+                "inputs of size {size} aren't bigger than inputs of size {last_size}: \
+                 {smallest} <= {largest}"
+            );
+        }
+        last = Some((size, *measured.iter().max().expect("some seeds")));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_ranges() {
+        let mut rng = Rng::new(42);
+        for _ in 0..1000 {
+            assert!(rng.below(7) < 7);
+            assert!((10..=12).contains(&rng.between(10, 12)));
+            assert!((-3..=3).contains(&rng.between_i64(-3, 3)));
+        }
+        assert_eq!(rng.between(5, 5), 5);
+    }
+
+    #[test]
+    fn test_check_generator() {
+        fn generate(size: usize, rng: &mut Rng) -> String {
+            (0..size).map(|_| format!("{}\n", rng.below(100))).collect()
+        }
+        check_generator(generate, &[1, 10, 100], |input| Ok(input.lines().count()));
+    }
+
+    #[test]
+    #[should_panic(expected = "aren't bigger")]
+    fn test_check_generator_scaling() {
+        fn generate(_size: usize, rng: &mut Rng) -> String {
+            format!("{}\n", rng.next_u64())
+        }
+        check_generator(generate, &[1, 10], |input| Ok(input.lines().count()));
+    }
+
+    #[test]
+    fn test_reproducible() {
+        let (mut a, mut b) = (Rng::new(7), Rng::new(7));
+        for _ in 0..10 {
+            assert_eq!(a.next_u64(), b.next_u64());
+        }
+    }
+}
@@ -0,0 +1,246 @@
+use std::{
+    fmt::Display,
+    time::{Duration, Instant},
+};
+
+use color_eyre::{
+    eyre::{bail, eyre},
+    Result,
+};
+use nu_ansi_term::{Color, Style};
+
+use crate::{Day, Part};
+
+/// Smallest input size to try.
+const MIN_SIZE: usize = 16;
+/// Stop growing the input once a single run takes longer than this.
+const TIME_BUDGET: Duration = Duration::from_secs(1);
+/// Maximum number of sizes to try.
+const MAX_STEPS: usize = 20;
+/// Runs are repeated until they take at least this long in total, to smooth out noise on small
+/// inputs.
+const MIN_SAMPLE_TIME: Duration = Duration::from_millis(20);
+/// Don't name a best fit whose RMS relative error is above this, the timings don't follow any of
+/// the classes closely enough for it to mean anything.
+const MAX_FIT_ERROR: f64 = 0.25;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Complexity {
+    Constant,
+    Logarithmic,
+    Linear,
+    Linearithmic,
+    Quadratic,
+    Cubic,
+}
+
+impl Complexity {
+    pub const ALL: [Self; 6] = [
+        Self::Constant,
+        Self::Logarithmic,
+        Self::Linear,
+        Self::Linearithmic,
+        Self::Quadratic,
+        Self::Cubic,
+    ];
+
+    fn eval(&self, n: f64) -> f64 {
+        match self {
+            Self::Constant => 1.0,
+            Self::Logarithmic => n.log2(),
+            Self::Linear => n,
+            Self::Linearithmic => n * n.log2(),
+            Self::Quadratic => n * n,
+            Self::Cubic => n * n * n,
+        }
+    }
+}
+
+impl Display for Complexity {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let s = match self {
+            Self::Constant => "O(1)",
+            Self::Logarithmic => "O(log n)",
+            Self::Linear => "O(n)",
+            Self::Linearithmic => "O(n log n)",
+            Self::Quadratic => "O(n²)",
+            Self::Cubic => "O(n³)",
+        };
+        f.pad(s)
+    }
+}
+
+/// Fit `(size, seconds)` samples to each complexity class, returning the classes along with their
+/// fit error, best fit first.
+///
+/// Each class is fitted as `a + c * f(n)`, where `a` accounts for fixed costs (parsing setup,
+/// compiling regexes, ...) that would otherwise dominate small inputs. Since timings span several
+/// orders of magnitude, `a` and `c` are the non-negative values minimising the squared *relative*
+/// error, and the error reported is the RMS of the relative residuals.
+pub fn fit(samples: &[(f64, f64)]) -> Vec<(Complexity, f64)> {
+    let mut fits = Complexity::ALL
+        .into_iter()
+        .map(|class| {
+            // Minimising Σ (a/t + c·f/t - 1)² is a linear least squares problem in (a, c)
+            let (u, v): (Vec<_>, Vec<_>) = samples
+                .iter()
+                .map(|(n, t)| (1.0 / t, class.eval(*n) / t))
+                .unzip();
+            let dot = |x: &[f64], y: &[f64]| x.iter().zip(y).map(|(a, b)| a * b).sum::<f64>();
+            let (uu, uv, vv) = (dot(&u, &u), dot(&u, &v), dot(&v, &v));
+            let (su, sv) = (u.iter().sum::<f64>(), v.iter().sum::<f64>());
+            let sq_err_of = |(a, c): (f64, f64)| {
+                u.iter()
+                    .zip(&v)
+                    .map(|(u, v)| (a * u + c * v - 1.0).powi(2))
+                    .sum::<f64>()
+            };
+            // Negative costs make no sense, so this is a non-negative least squares problem: the
+            // best fit is either the unconstrained one if it is non-negative, or the best one
+            // with either parameter set to 0. For O(1), where both parameters are the same
+            // thing, only the latter are well defined.
+            let det = uu * vv - uv * uv;
+            let unconstrained = ((su * vv - sv * uv) / det, (sv * uu - su * uv) / det);
+            let interior = (det.abs() > f64::EPSILON * uu * vv
+                && unconstrained.0 >= 0.0
+                && unconstrained.1 >= 0.0)
+                .then_some(unconstrained);
+            let sq_err = [Some((su / uu, 0.0)), Some((0.0, sv / vv)), interior]
+                .into_iter()
+                .flatten()
+                .map(sq_err_of)
+                .fold(f64::INFINITY, f64::min);
+            (class, (sq_err / samples.len() as f64).sqrt())
+        })
+        .collect::<Vec<_>>();
+    // Errors within 0.1% of each other are considered equal, in which case the simplest class
+    // (first in `Complexity::ALL`) wins thanks to the sort being stable.
+    fits.sort_by(|(_, e1), (_, e2)| (e1 * 1000.0).round().total_cmp(&(e2 * 1000.0).round()));
+    fits
+}
+
+impl Day {
+    /// Run `part` on generated inputs of increasing size, and report which complexity class fits
+    /// the timings best.
+    pub fn scale(&self, part: Part) -> Result<()> {
+        if self.generator.is_none() {
+            println!("Day {:02} has no input generator!", self.n);
+            return Ok(());
+        }
+        println!(
+            "{}",
+            Color::Purple.underline().paint(format!(
+                "Day {} part {part} scaling",
+                Style::default().bold().paint(format!("{:02}", self.n))
+            ))
+        );
+        println!("{:>12} {:>12}", "size", "time");
+
+        let mut samples = vec![];
+        let mut size = MIN_SIZE;
+        let mut previous_len = 0;
+        for _ in 0..MAX_STEPS {
+            let input = self.generate(size, 0).expect("day has a generator");
+            // Timing the same input over and over would make any fit meaningless
+            if input.len() <= previous_len {
+                bail!(
+                    "The day {:02} generator doesn't grow past size {}",
+                    self.n,
+                    size / 2
+                );
+            }
+            previous_len = input.len();
+            let time = self.time_part(part, &input)?;
+            println!("{size:>12} {:>12}", format!("{time:.2?}"));
+            samples.push((size as f64, time.as_secs_f64()));
+            if time > TIME_BUDGET {
+                break;
+            }
+            size *= 2;
+        }
+
+        let fits = fit(&samples);
+        let (best, error) = fits[0];
+        let others = if error <= MAX_FIT_ERROR {
+            println!(
+                "Best fit: {} (error {:.1}%)",
+                Color::Green.bold().paint(best.to_string()),
+                error * 100.0
+            );
+            &fits[1..]
+        } else {
+            println!(
+                "{}",
+                Color::Red.bold().paint(format!(
+                    "No good fit (all errors above {:.0}%)",
+                    MAX_FIT_ERROR * 100.0
+                ))
+            );
+            &fits[..]
+        };
+        for (class, error) in others {
+            println!(
+                "{}",
+                Color::DarkGray.paint(format!("  {class:<10} {:>6.1}%", error * 100.0))
+            );
+        }
+
+        Ok(())
+    }
+
+    /// Average time taken by `part` on `input`.
+    fn time_part(&self, part: Part, input: &str) -> Result<Duration> {
+        let mut runs = 0;
+        let start = Instant::now();
+        while runs == 0 || start.elapsed() < MIN_SAMPLE_TIME {
+            self.part(part, input)
+                .map_err(|e| eyre!("Part {part} failed: {e}"))?;
+            runs += 1;
+        }
+        Ok(start.elapsed() / runs)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn samples(f: impl Fn(f64) -> f64) -> Vec<(f64, f64)> {
+        (4..16)
+            .map(|i| (1 << i) as f64)
+            .map(|n| (n, f(n)))
+            .collect()
+    }
+
+    #[test]
+    fn test_fit_linear() {
+        // Linear timings like real measurements: a few percent of noise, and the smallest inputs
+        // cheaper per item as they fit in cache, which pushes the fixed cost of the O(n) fit
+        // below 0. The O(n) fit must still win over classes growing faster.
+        let noise = [
+            1.0, 1.08, 0.93, 1.05, 0.9, 1.1, 0.97, 1.04, 0.92, 1.07, 0.95, 1.02,
+        ];
+        let samples = samples(|n| 2e-6 * n * if n < 128.0 { 0.85 } else { 1.0 })
+            .into_iter()
+            .zip(noise)
+            .map(|((n, t), noise)| (n, t * noise))
+            .collect::<Vec<_>>();
+        let fits = fit(&samples);
+        assert_eq!(fits[0].0, Complexity::Linear, "{fits:?}");
+        assert!(fits[0].1 < MAX_FIT_ERROR, "{fits:?}");
+    }
+
+    #[test]
+    fn test_fit() {
+        assert_eq!(fit(&samples(|_| 3.0))[0].0, Complexity::Constant);
+        assert_eq!(fit(&samples(|n| 2.0 * n))[0].0, Complexity::Linear);
+        assert_eq!(
+            fit(&samples(|n| 0.1 * n * n.log2()))[0].0,
+            Complexity::Linearithmic
+        );
+        assert_eq!(
+            fit(&samples(|n| n * n + 5.0 * n))[0].0,
+            Complexity::Quadratic
+        );
+    }
+}
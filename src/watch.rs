@@ -0,0 +1,166 @@
+use std::{
+    path::{Path, PathBuf},
+    thread,
+    time::{Duration, SystemTime},
+};
+
+use color_eyre::{eyre::Context, Result};
+use nu_ansi_term::Color;
+
+use crate::{Day, PartResult};
+
+const POLL_INTERVAL: Duration = Duration::from_millis(500);
+
+/// Polls a set of files for changes to their modification time.
+///
+/// This deliberately relies on nothing but `stat`, so it works the same on any filesystem
+/// (including bind mounts inside containers where inotify events don't propagate).
+pub struct Watcher {
+    files: Vec<(PathBuf, Option<SystemTime>)>,
+}
+
+impl Watcher {
+    pub fn new<P: AsRef<Path>>(paths: impl IntoIterator<Item = P>) -> Self {
+        let files = paths
+            .into_iter()
+            .map(|p| {
+                let p = p.as_ref().to_path_buf();
+                let mtime = mtime(&p);
+                (p, mtime)
+            })
+            .collect();
+        Self { files }
+    }
+
+    /// Returns the files whose modification time changed since the last poll. A file appearing
+    /// or disappearing counts as a change.
+    pub fn poll(&mut self) -> Vec<PathBuf> {
+        self.files
+            .iter_mut()
+            .filter_map(|(path, last)| {
+                let current = mtime(path);
+                (current != *last).then(|| {
+                    *last = current;
+                    path.clone()
+                })
+            })
+            .collect()
+    }
+
+    /// Blocks until at least one file changes, and returns the changed files.
+    pub fn wait(&mut self, interval: Duration) -> Vec<PathBuf> {
+        loop {
+            let changed = self.poll();
+            if !changed.is_empty() {
+                return changed;
+            }
+            thread::sleep(interval);
+        }
+    }
+}
+
+fn mtime(path: &Path) -> Option<SystemTime> {
+    std::fs::metadata(path).and_then(|m| m.modified()).ok()
+}
+
+/// Results of running a day against one input file.
+struct Run {
+    path: PathBuf,
+    results: Result<Vec<PartResult>>,
+}
+
+impl Day {
+    /// Re-run both parts every time the input (or example) file changes, keeping the results of
+    /// the previous run on screen for comparison.
+    pub fn watch(&self) -> Result<()> {
+        let source = self.source_path();
+        let mut watcher = Watcher::new([self.input_path(), self.example_path(), source.clone()]);
+        let mut previous: Option<Vec<Run>> = None;
+        let mut source_changed = false;
+
+        loop {
+            let runs = self.watch_runs();
+
+            // Clear the screen and move the cursor back to the top-left corner
+            print!("\x1b[2J\x1b[H");
+            self.log_day();
+            self.log_runs(&runs);
+            if let Some(previous) = &previous {
+                println!();
+                println!("{}", Color::DarkGray.underline().paint("Previous run"));
+                self.log_runs(previous);
+            }
+            println!();
+            if source_changed {
+                println!(
+                    "{}",
+                    Color::Yellow.paint(format!(
+                        "{} changed: rebuild to pick up the new code",
+                        source.display()
+                    ))
+                );
+            }
+            println!(
+                "{}",
+                Color::DarkGray.paint("Watching for changes... (Ctrl-C to quit)")
+            );
+            previous = Some(runs);
+
+            // Only report the source change that triggered this run, so that later input changes
+            // don't keep asking for a rebuild that was already done
+            let changed = watcher.wait(POLL_INTERVAL);
+            source_changed = changed.contains(&source);
+        }
+    }
+
+    fn watch_runs(&self) -> Vec<Run> {
+        let mut runs = vec![Run {
+            path: self.input_path(),
+            results: self
+                .load_input()
+                .map(|input| self.solve_parts(None, &input)),
+        }];
+
+        let example = self.example_path();
+        if example.exists() {
+            runs.push(Run {
+                results: std::fs::read_to_string(&example)
+                    .wrap_err("Failed to load example file")
+                    .map(|input| self.solve_parts(None, &input)),
+                path: example,
+            });
+        }
+        runs
+    }
+
+    fn log_runs(&self, runs: &[Run]) {
+        for run in runs {
+            println!("{}", Color::Cyan.paint(run.path.display().to_string()));
+            match &run.results {
+                Ok(results) => self.log_results(results),
+                Err(e) => println!(" → {}", Color::Red.paint(e.to_string())),
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_poll() {
+        let path = std::env::temp_dir().join(format!("aoc2023-watch-{}", std::process::id()));
+        let _ = std::fs::remove_file(&path);
+
+        let mut watcher = Watcher::new([&path]);
+        assert!(watcher.poll().is_empty());
+
+        std::fs::write(&path, "1").unwrap();
+        assert_eq!(watcher.poll(), vec![path.clone()]);
+        assert!(watcher.poll().is_empty());
+
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!(watcher.poll(), vec![path]);
+    }
+}